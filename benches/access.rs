@@ -0,0 +1,72 @@
+//! Compares the cost of the thread checks in the wrappers with the cost of
+//! comparing stdlib thread ids which is what the wrappers used to do.
+//!
+//! Run with `cargo bench`.
+use std::mem;
+use std::ptr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use fragile::{Fragile, Sticky};
+
+const ITERATIONS: u32 = 10_000_000;
+
+/// `std::hint::black_box` is not available on the minimum supported version.
+fn black_box<T>(dummy: T) -> T {
+    unsafe {
+        let rv = ptr::read_volatile(&dummy);
+        mem::forget(dummy);
+        rv
+    }
+}
+
+fn bench<F: FnMut()>(name: &str, mut f: F) -> Duration {
+    // warm up caches and lazily initialized thread locals
+    for _ in 0..ITERATIONS / 100 {
+        f();
+    }
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{:<32} {:>8.2} ns/iter",
+        name,
+        elapsed.as_nanos() as f64 / ITERATIONS as f64
+    );
+    elapsed
+}
+
+fn main() {
+    let owner = thread::current().id();
+    bench("baseline: thread::current().id()", || {
+        black_box(black_box(thread::current().id()) == owner);
+    });
+
+    let fragile = Fragile::new(42u64);
+    bench("Fragile::is_valid", || {
+        black_box(black_box(&fragile).is_valid());
+    });
+    bench("Fragile::get", || {
+        black_box(*black_box(&fragile).get());
+    });
+    bench("Fragile::try_get", || {
+        black_box(black_box(&fragile).try_get().is_ok());
+    });
+
+    let sticky = Sticky::new(42u64);
+    // a few extra entries so the registry lookup is not trivial
+    let _others: Vec<_> = (0..1000).map(Sticky::new).collect();
+    bench("Sticky::is_valid", || {
+        black_box(black_box(&sticky).is_valid());
+    });
+    bench("Sticky::get", || {
+        fragile::stack_token!(tok);
+        black_box(*black_box(&sticky).get(tok));
+    });
+    bench("Sticky::try_get", || {
+        fragile::stack_token!(tok);
+        black_box(black_box(&sticky).try_get(tok).is_ok());
+    });
+}
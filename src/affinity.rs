@@ -0,0 +1,257 @@
+use std::cell::Cell;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::thread_id;
+
+/// Identifies the context that owns the value of a [`Fragile`](crate::Fragile).
+///
+/// A [`Fragile`](crate::Fragile) remembers the context that was current when
+/// it was created and only grants access to the value while that context is
+/// current again.  By default the context is the thread ([`ThreadAffinity`]),
+/// but values can also be bound to a task ([`TaskAffinity`]) or to any other
+/// user defined context.
+///
+/// # Safety
+///
+/// An id returned by [`current`](Self::current) must never be current on two
+/// threads at the same time, and ids must never be reused.
+pub unsafe trait Affinity: 'static {
+    /// Returns the id of the current context if there is one.
+    fn current() -> Option<NonZeroUsize>;
+}
+
+/// Binds values to the thread that created them.
+///
+/// This is the default affinity of [`Fragile`](crate::Fragile).
+#[derive(Debug)]
+pub struct ThreadAffinity;
+
+unsafe impl Affinity for ThreadAffinity {
+    #[inline(always)]
+    fn current() -> Option<NonZeroUsize> {
+        thread_id::try_get()
+    }
+}
+
+/// Binds values to a task rather than a thread.
+///
+/// A task is entered with [`scope`](Self::scope) or, for futures, with
+/// [`bind`](Self::bind).  A [`Fragile`](crate::Fragile) created with this
+/// affinity can be accessed whenever the task it was created in is current,
+/// no matter which thread the task is running on.  This makes it possible to
+/// use such values with work-stealing executors as long as the value stays
+/// within its task.
+///
+/// ```
+/// use fragile::{Fragile, TaskAffinity};
+///
+/// TaskAffinity::scope(|| {
+///     // SAFETY: the value does not share state with anything outside the task
+///     let val = unsafe { Fragile::<_, TaskAffinity>::with_affinity(vec![1, 2, 3]) };
+///     assert_eq!(val.get().len(), 3);
+///     TaskAffinity::scope(|| assert!(!val.is_valid()));
+/// });
+/// ```
+#[derive(Debug)]
+pub struct TaskAffinity;
+
+thread_local!(static CURRENT_TASK: Cell<Option<NonZeroUsize>> = Cell::new(None));
+
+unsafe impl Affinity for TaskAffinity {
+    #[inline(always)]
+    fn current() -> Option<NonZeroUsize> {
+        CURRENT_TASK.try_with(|x| x.get()).ok().flatten()
+    }
+}
+
+fn next_task_id() -> NonZeroUsize {
+    static COUNTER: AtomicUsize = AtomicUsize::new(1);
+    NonZeroUsize::new(COUNTER.fetch_add(1, Ordering::Relaxed)).expect("more than usize::MAX tasks")
+}
+
+/// Makes a task current until dropped, restoring the previous one.
+struct Enter(Option<NonZeroUsize>);
+
+impl Enter {
+    fn new(id: NonZeroUsize) -> Enter {
+        Enter(CURRENT_TASK.with(|x| x.replace(Some(id))))
+    }
+}
+
+impl Drop for Enter {
+    fn drop(&mut self) {
+        CURRENT_TASK.with(|x| x.set(self.0));
+    }
+}
+
+impl TaskAffinity {
+    /// Runs `f` in a new task.
+    ///
+    /// Values bound to the task while `f` runs can no longer be accessed once
+    /// this returns.
+    pub fn scope<R, F: FnOnce() -> R>(f: F) -> R {
+        let _enter = Enter::new(next_task_id());
+        f()
+    }
+
+    /// Binds a future to a new task.
+    ///
+    /// The task is current whenever the returned future is polled.
+    #[cfg(feature = "future")]
+    pub fn bind<F: std::future::Future>(future: F) -> TaskBound<F> {
+        TaskBound {
+            id: next_task_id(),
+            future,
+        }
+    }
+}
+
+/// A future bound to a task with [`TaskAffinity::bind`].
+#[cfg(feature = "future")]
+#[derive(Debug)]
+pub struct TaskBound<F> {
+    id: NonZeroUsize,
+    future: F,
+}
+
+#[cfg(feature = "future")]
+impl<F: std::future::Future> std::future::Future for TaskBound<F> {
+    type Output = F::Output;
+
+    fn poll(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        let _enter = Enter::new(self.id);
+        // SAFETY: the future is never moved out of the pinned wrapper.
+        unsafe { self.map_unchecked_mut(|x| &mut x.future) }.poll(cx)
+    }
+}
+
+#[test]
+fn test_scope() {
+    use crate::Fragile;
+
+    assert_eq!(TaskAffinity::current(), None);
+    TaskAffinity::scope(|| {
+        let outer = TaskAffinity::current().unwrap();
+        let val = unsafe { Fragile::<_, TaskAffinity>::with_affinity(true) };
+        assert!(val.is_valid());
+        TaskAffinity::scope(|| {
+            assert_ne!(TaskAffinity::current(), Some(outer));
+            assert!(val.try_get().is_err());
+        });
+        assert_eq!(TaskAffinity::current(), Some(outer));
+        assert!(*val.get());
+    });
+    assert_eq!(TaskAffinity::current(), None);
+}
+
+#[cfg(feature = "future")]
+#[test]
+fn test_task_moves_between_threads() {
+    use crate::Fragile;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::rc::Rc;
+    use std::task::{Context, Poll};
+    use std::thread;
+
+    // yields once so that the task can be moved to another thread.
+    struct YieldOnce(bool);
+    impl Future for YieldOnce {
+        type Output = ();
+        fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                Poll::Ready(())
+            } else {
+                self.0 = true;
+                Poll::Pending
+            }
+        }
+    }
+
+    let mut task = Box::pin(TaskAffinity::bind(async {
+        // SAFETY: the rc never leaves the task
+        let val = unsafe { Fragile::<_, TaskAffinity>::with_affinity(Rc::new(42)) };
+        let thread = thread::current().id();
+        YieldOnce(false).await;
+        assert_ne!(thread::current().id(), thread);
+        **val.get()
+    }));
+
+    let waker = futures_util::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    assert!(task.as_mut().poll(&mut cx).is_pending());
+    let rv = thread::spawn(move || {
+        let waker = futures_util::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        task.as_mut().poll(&mut cx)
+    })
+    .join()
+    .unwrap();
+    assert_eq!(rv, Poll::Ready(42));
+}
+
+#[cfg(feature = "future")]
+#[test]
+fn test_task_does_not_reclaim_foreign_graves() {
+    use crate::Fragile;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::rc::Rc;
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll};
+    use std::thread::{self, ThreadId};
+
+    struct YieldOnce(bool);
+    impl Future for YieldOnce {
+        type Output = ();
+        fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                Poll::Ready(())
+            } else {
+                self.0 = true;
+                Poll::Pending
+            }
+        }
+    }
+
+    // records the thread that runs the destructor.
+    struct RecordDrop(Arc<Mutex<Option<ThreadId>>>);
+    impl Drop for RecordDrop {
+        fn drop(&mut self) {
+            *self.0.lock().unwrap() = Some(thread::current().id());
+        }
+    }
+
+    let mut task = Box::pin(TaskAffinity::bind(async {
+        // SAFETY: the rc never leaves the task
+        let val = unsafe { Fragile::<_, TaskAffinity>::with_affinity(Rc::new(42)) };
+        YieldOnce(false).await;
+        **val.get()
+    }));
+    let waker = futures_util::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    assert!(task.as_mut().poll(&mut cx).is_pending());
+
+    // bury a value for the home of this thread which the task shares.
+    let dropped_on = Arc::new(Mutex::new(None));
+    let deferred = Fragile::new_deferred(RecordDrop(dropped_on.clone()));
+    thread::spawn(move || drop(deferred)).join().unwrap();
+    assert_eq!(*dropped_on.lock().unwrap(), None);
+
+    let rv = thread::spawn(move || {
+        let waker = futures_util::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        task.as_mut().poll(&mut cx)
+    })
+    .join()
+    .unwrap();
+    assert_eq!(rv, Poll::Ready(42));
+    assert_eq!(*dropped_on.lock().unwrap(), None);
+
+    drop(Fragile::new(()));
+    assert_eq!(*dropped_on.lock().unwrap(), Some(thread::current().id()));
+}
@@ -1,14 +1,275 @@
 use std::error;
 use std::fmt;
+use std::panic::Location;
+use std::thread::ThreadId;
+
+use crate::home::Home;
+use crate::thread_id;
+
+/// The kind of wrapper a value is stored in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum WrapperKind {
+    /// The value is stored in a [`Fragile`](crate::Fragile).
+    Fragile,
+    /// The value is stored in a [`Sticky`](crate::Sticky).
+    Sticky,
+    /// The value is stored in a [`SemiSticky`](crate::SemiSticky).
+    SemiSticky,
+    /// The value is referred to by a [`StickyKey`](crate::StickyKey).
+    StickyKey,
+    /// The value is stored in a [`PerThread`](crate::PerThread).
+    PerThread,
+}
+
+impl fmt::Display for WrapperKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            WrapperKind::Fragile => "fragile",
+            WrapperKind::Sticky => "sticky",
+            WrapperKind::SemiSticky => "semi-sticky",
+            WrapperKind::StickyKey => "sticky key",
+            WrapperKind::PerThread => "per-thread",
+        })
+    }
+}
+
+/// The reason why an access failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum AccessErrorReason {
+    /// The value was accessed from a thread other than the owning thread.
+    WrongThread,
+    /// The value was accessed after the owning thread exited.
+    OwnerExited,
+    /// The thread local registry holding the value was already destroyed.
+    RegistryDestroyed,
+    /// The value was already taken out.
+    AlreadyTaken,
+    /// The value cannot be accessed mutably or taken out because it is
+    /// shared with or borrowed through weak handles.
+    Borrowed,
+    /// The value was destroyed when the [`Scope`](crate::Scope) it was created
+    /// in ended.
+    ScopeEnded,
+    /// The value belongs to a [`Scope`](crate::Scope) that was entered after
+    /// the [`StackToken`](crate::StackToken) used to borrow it was created.
+    OuterToken,
+}
 
 /// Returned when borrowing fails.
-#[derive(Debug)]
-pub struct InvalidThreadAccess;
+#[derive(Debug, Clone)]
+pub struct InvalidThreadAccess {
+    reason: AccessErrorReason,
+    wrapper: WrapperKind,
+    type_name: &'static str,
+    owner: ThreadId,
+    owner_name: Option<String>,
+    current: Option<ThreadId>,
+    location: &'static Location<'static>,
+}
+
+impl InvalidThreadAccess {
+    #[cold]
+    #[track_caller]
+    pub(crate) fn new<T: ?Sized>(wrapper: WrapperKind, home: &Home) -> InvalidThreadAccess {
+        let reason = if home.is_alive() {
+            AccessErrorReason::WrongThread
+        } else {
+            AccessErrorReason::OwnerExited
+        };
+        InvalidThreadAccess::with_reason::<T>(reason, wrapper, home)
+    }
+
+    #[cold]
+    #[track_caller]
+    pub(crate) fn with_reason<T: ?Sized>(
+        reason: AccessErrorReason,
+        wrapper: WrapperKind,
+        home: &Home,
+    ) -> InvalidThreadAccess {
+        InvalidThreadAccess::for_owner::<T>(
+            reason,
+            wrapper,
+            home.thread_id(),
+            home.thread_name().map(|x| x.to_string()),
+        )
+    }
+
+    #[cold]
+    #[track_caller]
+    pub(crate) fn for_owner<T: ?Sized>(
+        reason: AccessErrorReason,
+        wrapper: WrapperKind,
+        owner: ThreadId,
+        owner_name: Option<String>,
+    ) -> InvalidThreadAccess {
+        InvalidThreadAccess {
+            reason,
+            wrapper,
+            type_name: std::any::type_name::<T>(),
+            owner,
+            owner_name,
+            current: thread_id::try_current(),
+            location: Location::caller(),
+        }
+    }
+
+    pub(crate) fn set_wrapper(mut self, wrapper: WrapperKind) -> InvalidThreadAccess {
+        self.wrapper = wrapper;
+        self
+    }
+
+    /// Why the access failed.
+    pub fn reason(&self) -> AccessErrorReason {
+        self.reason
+    }
+
+    /// The kind of wrapper holding the value.
+    pub fn wrapper(&self) -> WrapperKind {
+        self.wrapper
+    }
+
+    /// The name of the wrapped type.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// The ID of the thread that owns the value.
+    pub fn owner(&self) -> ThreadId {
+        self.owner
+    }
+
+    /// The name of the thread that owns the value if it has one.
+    pub fn owner_name(&self) -> Option<&str> {
+        self.owner_name.as_deref()
+    }
+
+    /// The ID of the thread that attempted the access.
+    ///
+    /// This is `None` if the access happened while the thread was shutting
+    /// down and its ID could no longer be determined.
+    pub fn current(&self) -> Option<ThreadId> {
+        self.current
+    }
+
+    /// The location of the failed access.
+    pub fn location(&self) -> &'static Location<'static> {
+        self.location
+    }
+}
 
 impl fmt::Display for InvalidThreadAccess {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "fragile value accessed from foreign thread")
+        match self.reason {
+            AccessErrorReason::WrongThread => {
+                write!(f, "{} value accessed from foreign thread", self.wrapper)?
+            }
+            AccessErrorReason::OwnerExited => write!(
+                f,
+                "{} value accessed after its owning thread exited",
+                self.wrapper
+            )?,
+            AccessErrorReason::RegistryDestroyed => write!(
+                f,
+                "{} value accessed after the thread local registry was destroyed",
+                self.wrapper
+            )?,
+            AccessErrorReason::AlreadyTaken => {
+                write!(f, "{} value was already taken", self.wrapper)?
+            }
+            AccessErrorReason::Borrowed => write!(
+                f,
+                "{} value is shared with or borrowed through weak handles",
+                self.wrapper
+            )?,
+            AccessErrorReason::ScopeEnded => {
+                write!(f, "{} value accessed after its scope ended", self.wrapper)?
+            }
+            AccessErrorReason::OuterToken => write!(
+                f,
+                "{} value borrowed with a stack token from outside of its scope",
+                self.wrapper
+            )?,
+        }
+        write!(f, " (type: {}, owner: ", self.type_name)?;
+        if let Some(ref name) = self.owner_name {
+            write!(f, "'{}' ", name)?;
+        }
+        write!(f, "{:?}, at {})", self.owner, self.location)
     }
 }
 
 impl error::Error for InvalidThreadAccess {}
+
+/// Returned when taking the value out of a wrapper fails.
+///
+/// This hands back the wrapper together with the reason why the value could
+/// not be taken out.
+pub struct IntoInnerError<W> {
+    wrapper: W,
+    error: InvalidThreadAccess,
+}
+
+impl<W> IntoInnerError<W> {
+    pub(crate) fn new(wrapper: W, error: InvalidThreadAccess) -> IntoInnerError<W> {
+        IntoInnerError { wrapper, error }
+    }
+
+    /// Returns the error explaining why the value could not be taken out.
+    pub fn error(&self) -> &InvalidThreadAccess {
+        &self.error
+    }
+
+    /// Returns the wrapper, discarding the error.
+    pub fn into_inner(self) -> W {
+        self.wrapper
+    }
+
+    /// Returns the wrapper together with the error.
+    pub fn into_parts(self) -> (W, InvalidThreadAccess) {
+        (self.wrapper, self.error)
+    }
+
+    pub(crate) fn map<V, F: FnOnce(W) -> V>(self, f: F) -> IntoInnerError<V> {
+        IntoInnerError {
+            wrapper: f(self.wrapper),
+            error: self.error,
+        }
+    }
+}
+
+impl<W> fmt::Debug for IntoInnerError<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("IntoInnerError")
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+impl<W> fmt::Display for IntoInnerError<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl<W> error::Error for IntoInnerError<W> {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Returned when a closure scheduled on a [`Home`](crate::Home) will never run.
+///
+/// This happens if the home thread shut down before it got around to running
+/// the closure or if the closure panicked.
+#[derive(Debug)]
+pub struct TaskAbandoned;
+
+impl fmt::Display for TaskAbandoned {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "task scheduled on home thread was abandoned")
+    }
+}
+
+impl error::Error for TaskAbandoned {}
@@ -0,0 +1,456 @@
+//! A minimal single threaded executor.
+//!
+//! The [`LocalExecutor`] runs futures that are not `Send` on the thread that
+//! created it.  Other threads can hand it work through a [`Spawner`] which is
+//! `Send` and `Clone`.  Instead of a future, the spawner accepts a closure that
+//! constructs the future on the executor thread so that the future itself
+//! never has to cross a thread boundary.  The output is delivered through a
+//! `Send` [`JoinHandle`].
+//!
+//! While the executor runs it also pumps the [`Home`] of its thread, so work
+//! queued with `run_on_home` or polls handed off by a
+//! [`HomeBound`](crate::HomeBound) make progress as well.
+//!
+//! ```
+//! use std::rc::Rc;
+//! use std::thread;
+//! use fragile::executor::LocalExecutor;
+//!
+//! let executor = LocalExecutor::new();
+//! let spawner = executor.spawner();
+//! let handle = thread::spawn(move || {
+//!     spawner.spawn(|| async {
+//!         let value = Rc::new(21);
+//!         *value * 2
+//!     })
+//! })
+//! .join()
+//! .unwrap();
+//! assert_eq!(executor.block_on(handle).unwrap(), 42);
+//! ```
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread;
+
+use crate::errors::TaskAbandoned;
+use crate::home::{self, Home, HomeTask};
+
+type LocalTask = Pin<Box<dyn Future<Output = ()>>>;
+type Spawn = Box<dyn FnOnce() -> LocalTask + Send>;
+
+/// The id that wakes the future passed to [`LocalExecutor::block_on`].
+const MAIN_TASK: usize = 0;
+
+struct Shared {
+    home: Home,
+    state: Mutex<State>,
+}
+
+struct State {
+    ready: VecDeque<usize>,
+    incoming: VecDeque<Spawn>,
+    closed: bool,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // no foreign code runs while the lock is held
+        match self.state.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+struct TaskWaker {
+    id: usize,
+    shared: Arc<Shared>,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        let mut state = self.shared.lock();
+        if !state.closed {
+            state.ready.push_back(self.id);
+            drop(state);
+            self.shared.home.unpark();
+        }
+    }
+}
+
+/// An executor for futures that are not `Send`.
+///
+/// See the [module level documentation](self) for more information.
+pub struct LocalExecutor {
+    shared: Arc<Shared>,
+    tasks: RefCell<HashMap<usize, LocalTask>>,
+    next_id: Cell<usize>,
+}
+
+impl LocalExecutor {
+    /// Creates an executor for the current thread.
+    pub fn new() -> LocalExecutor {
+        LocalExecutor {
+            shared: Arc::new(Shared {
+                home: Home::current(),
+                state: Mutex::new(State {
+                    ready: VecDeque::new(),
+                    incoming: VecDeque::new(),
+                    closed: false,
+                }),
+            }),
+            tasks: RefCell::new(HashMap::new()),
+            next_id: Cell::new(MAIN_TASK + 1),
+        }
+    }
+
+    /// Returns a [`Spawner`] that can submit work from any thread.
+    pub fn spawner(&self) -> Spawner {
+        Spawner {
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Spawns a future from the executor thread.
+    ///
+    /// The future runs the next time the executor is driven.
+    pub fn spawn_local<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + 'static,
+    {
+        let (task, completer) = HomeTask::new(self.shared.home.clone());
+        self.insert(Box::pin(async move { completer.complete(future.await) }));
+        JoinHandle { task }
+    }
+
+    fn insert(&self, task: LocalTask) {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        self.tasks.borrow_mut().insert(id, task);
+        self.shared.lock().ready.push_back(id);
+    }
+
+    fn waker(&self, id: usize) -> Waker {
+        Waker::from(Arc::new(TaskWaker {
+            id,
+            shared: self.shared.clone(),
+        }))
+    }
+
+    /// Pumps the home and polls all spawned tasks that are ready.
+    ///
+    /// Returns the amount of work done and whether the main task was woken.
+    fn tick(&self) -> (usize, bool) {
+        let (incoming, ready) = {
+            let state = self.shared.lock();
+            (state.incoming.len(), state.ready.len())
+        };
+        let mut ran = home::pump();
+        // work is taken one at a time so that the rest stays queued if one
+        // of the closures or tasks panics.
+        for _ in 0..incoming {
+            let spawn = match self.shared.lock().incoming.pop_front() {
+                Some(spawn) => spawn,
+                None => break,
+            };
+            self.insert(spawn());
+            ran += 1;
+        }
+
+        let mut main_woken = false;
+        for _ in 0..ready {
+            let id = match self.shared.lock().ready.pop_front() {
+                Some(id) => id,
+                None => break,
+            };
+            if id == MAIN_TASK {
+                main_woken = true;
+                continue;
+            }
+            // the task is taken out while it is polled so that it can spawn
+            // other tasks and so that it is dropped if it panics.
+            let task = self.tasks.borrow_mut().remove(&id);
+            if let Some(mut task) = task {
+                let waker = self.waker(id);
+                if task
+                    .as_mut()
+                    .poll(&mut Context::from_waker(&waker))
+                    .is_pending()
+                {
+                    self.tasks.borrow_mut().insert(id, task);
+                }
+                ran += 1;
+            }
+        }
+        (ran, main_woken)
+    }
+
+    /// Runs spawned tasks until none of them can make progress.
+    ///
+    /// Returns the number of tasks that were polled and closures that were
+    /// pumped from the home of the thread.
+    pub fn run_until_stalled(&self) -> usize {
+        let mut total = 0;
+        loop {
+            match self.tick() {
+                (0, _) => return total,
+                (ran, _) => total += ran,
+            }
+        }
+    }
+
+    /// Runs spawned tasks until `future` completes and returns its output.
+    ///
+    /// The thread is parked while there is nothing to do.
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        let mut future = Box::pin(future);
+        let waker = self.waker(MAIN_TASK);
+        let mut main_woken = true;
+        loop {
+            if main_woken {
+                if let Poll::Ready(rv) = future.as_mut().poll(&mut Context::from_waker(&waker)) {
+                    return rv;
+                }
+            }
+            let (ran, woken) = self.tick();
+            main_woken = woken;
+            if ran == 0 && !woken {
+                thread::park();
+            }
+        }
+    }
+}
+
+impl Default for LocalExecutor {
+    fn default() -> LocalExecutor {
+        LocalExecutor::new()
+    }
+}
+
+impl Drop for LocalExecutor {
+    fn drop(&mut self) {
+        let incoming = {
+            let mut state = self.shared.lock();
+            state.closed = true;
+            state.ready.clear();
+            std::mem::take(&mut state.incoming)
+        };
+        // abandons the join handles of everything that did not finish.
+        drop(incoming);
+        drop(std::mem::take(self.tasks.get_mut()));
+    }
+}
+
+impl fmt::Debug for LocalExecutor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LocalExecutor")
+            .field("home", &self.shared.home)
+            .field("tasks", &self.tasks.borrow().len())
+            .finish()
+    }
+}
+
+/// Submits work to a [`LocalExecutor`] from any thread.
+#[derive(Clone)]
+pub struct Spawner {
+    shared: Arc<Shared>,
+}
+
+impl Spawner {
+    /// Spawns the future returned by `f` on the executor.
+    ///
+    /// `f` is invoked on the executor thread, so the future it returns does
+    /// not have to be `Send`.  If the executor was dropped, `f` is dropped and
+    /// the returned handle resolves to [`TaskAbandoned`].
+    pub fn spawn<F, Fut>(&self, f: F) -> JoinHandle<Fut::Output>
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future + 'static,
+        Fut::Output: Send + 'static,
+    {
+        let (task, completer) = HomeTask::new(self.shared.home.clone());
+        let spawn: Spawn = Box::new(move || {
+            let future = f();
+            Box::pin(async move { completer.complete(future.await) })
+        });
+        let mut state = self.shared.lock();
+        if state.closed {
+            drop(state);
+            drop(spawn);
+        } else {
+            state.incoming.push_back(spawn);
+            drop(state);
+            self.shared.home.unpark();
+        }
+        JoinHandle { task }
+    }
+
+    /// Returns the [`Home`] of the executor thread.
+    pub fn home(&self) -> &Home {
+        &self.shared.home
+    }
+}
+
+impl fmt::Debug for Spawner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Spawner")
+            .field("home", &self.shared.home)
+            .finish()
+    }
+}
+
+/// A handle to the output of a task spawned on a [`LocalExecutor`].
+///
+/// The handle is `Send` if the output is and can be awaited on any thread.
+/// It resolves to [`TaskAbandoned`] if the task panicked or the executor was
+/// dropped before the task finished.  Dropping the handle does not cancel
+/// the task.
+pub struct JoinHandle<R> {
+    task: HomeTask<R>,
+}
+
+impl<R> JoinHandle<R> {
+    /// Returns `true` if the task finished or was abandoned.
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+impl<R> Future for JoinHandle<R> {
+    type Output = Result<R, TaskAbandoned>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.task).poll(cx)
+    }
+}
+
+impl<R> fmt::Debug for JoinHandle<R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("JoinHandle")
+            .field("finished", &self.is_finished())
+            .finish()
+    }
+}
+
+#[test]
+fn test_spawn_local() {
+    use crate::{Fragile, Sticky};
+    use std::rc::Rc;
+
+    let executor = LocalExecutor::new();
+    let value = Rc::new(20);
+    let a = executor.spawn_local(Fragile::new({
+        let value = value.clone();
+        async move { *value + 1 }
+    }));
+    let b = executor.spawn_local(Sticky::new(async move { *value + 2 }));
+    assert!(!a.is_finished());
+    assert_eq!(executor.run_until_stalled(), 2);
+    assert_eq!(executor.block_on(a).unwrap(), 21);
+    assert_eq!(executor.block_on(b).unwrap(), 22);
+}
+
+#[test]
+fn test_spawn_from_other_thread() {
+    use std::rc::Rc;
+    use std::sync::mpsc::channel;
+
+    let executor = LocalExecutor::new();
+    let spawner = executor.spawner();
+    let (tx, rx) = channel();
+    let t = thread::spawn(move || {
+        let handle = spawner.spawn(|| async {
+            let value = Rc::new(thread::current().id());
+            *value
+        });
+        tx.send(()).unwrap();
+        futures_executor::block_on(handle).unwrap()
+    });
+    rx.recv().unwrap();
+    while executor.run_until_stalled() == 0 {
+        thread::yield_now();
+    }
+    assert_eq!(t.join().unwrap(), thread::current().id());
+}
+
+#[test]
+fn test_wakes_parked_executor() {
+    use futures_util::task::AtomicWaker;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    let executor = LocalExecutor::new();
+    let signal = Arc::new((AtomicBool::new(false), AtomicWaker::new()));
+    let t = thread::spawn({
+        let signal = signal.clone();
+        move || {
+            thread::sleep(Duration::from_millis(20));
+            signal.0.store(true, Ordering::SeqCst);
+            signal.1.wake();
+        }
+    });
+    // the executor parks until the main task is woken from the other thread
+    executor.block_on(futures_util::future::poll_fn(|cx| {
+        signal.1.register(cx.waker());
+        if signal.0.load(Ordering::SeqCst) {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_panicking_task() {
+    use futures_util::task::AtomicWaker;
+    use std::panic;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let executor = LocalExecutor::new();
+    let signal = Arc::new((AtomicBool::new(false), AtomicWaker::new()));
+    let woken = executor.spawn_local({
+        let signal = signal.clone();
+        futures_util::future::poll_fn(move |cx| {
+            signal.1.register(cx.waker());
+            if signal.0.load(Ordering::SeqCst) {
+                Poll::Ready(42)
+            } else {
+                Poll::Pending
+            }
+        })
+    });
+    assert_eq!(executor.run_until_stalled(), 1);
+
+    // the panicking task is polled before its woken sibling.
+    let panicking = executor.spawn_local(async { panic!("boom") });
+    signal.0.store(true, Ordering::SeqCst);
+    signal.1.wake();
+    let rv = panic::catch_unwind(panic::AssertUnwindSafe(|| executor.run_until_stalled()));
+    assert!(rv.is_err());
+
+    assert_eq!(executor.run_until_stalled(), 1);
+    assert_eq!(executor.block_on(woken).unwrap(), 42);
+    assert!(executor.block_on(panicking).is_err());
+}
+
+#[test]
+fn test_abandoned() {
+    let executor = LocalExecutor::new();
+    let spawner = executor.spawner();
+    let pending = executor.spawn_local(std::future::pending::<()>());
+    let queued = spawner.spawn(|| async {});
+    drop(executor);
+    assert!(futures_executor::block_on(pending).is_err());
+    assert!(futures_executor::block_on(queued).is_err());
+    assert!(futures_executor::block_on(spawner.spawn(|| async {})).is_err());
+}
@@ -1,10 +1,21 @@
+use std::any;
 use std::cmp;
+use std::convert::TryFrom;
+use std::error;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::marker::PhantomData;
 use std::mem;
-use std::thread;
-use std::thread::ThreadId;
-
-use crate::errors::InvalidThreadAccess;
+use std::num::NonZeroUsize;
+use std::ptr;
+
+use crate::affinity::{Affinity, ThreadAffinity};
+use crate::errors::{IntoInnerError, InvalidThreadAccess, WrapperKind};
+use crate::handoff::{Handoff, ThreadMovable};
+use crate::home::{self, Home, HomeTask};
+use crate::policy::{self, ViolationInfo, ViolationKind, ViolationPolicy};
+use crate::sticky::Sticky;
 use std::mem::ManuallyDrop;
 
 /// A [`Fragile<T>`] wraps a non sendable `T` to be safely send to other threads.
@@ -13,15 +24,29 @@ use std::mem::ManuallyDrop;
 /// to the value on those threads will fail.
 ///
 /// If the value needs destruction and the fragile wrapper is on another thread
-/// the destructor will panic.  Alternatively you can use
-/// [`Sticky`](crate::Sticky) which is not going to panic but might temporarily
-/// leak the value.
-pub struct Fragile<T> {
+/// the destructor will panic.  Alternatively you can create it with
+/// [`new_deferred`](Self::new_deferred) or use [`Sticky`](crate::Sticky) which
+/// are not going to panic but might temporarily leak the value.
+///
+/// By default the value is bound to a thread.  The second type parameter
+/// selects a different [`Affinity`], for instance [`TaskAffinity`](crate::TaskAffinity)
+/// to bind the value to a task which might move between the threads of an
+/// executor.  Such values are created with [`with_affinity`](Self::with_affinity).
+pub struct Fragile<T, A: Affinity = ThreadAffinity> {
     // ManuallyDrop is necessary because we need to move out of here without running the
     // Drop code in functions like `into_inner`.
     value: ManuallyDrop<T>,
-    // we can use ThreadId because Rust guarnatees it to be unique for the duration of a process.
-    thread_id: ThreadId,
+    // id of the owning context as returned by the affinity.  For threads this
+    // is the cached thread id.  The stdlib's `ThreadId` is only kept in the
+    // home for diagnostics as getting to it requires `thread::current()`.
+    owner: NonZeroUsize,
+    // the home of the thread that created the value.
+    home: Home,
+    // set for values that are handed back to their home when dropped on a
+    // foreign thread.
+    bury: Option<unsafe fn(&Home, &mut ManuallyDrop<T>)>,
+    policy: Option<ViolationPolicy>,
+    _affinity: PhantomData<fn() -> A>,
 }
 
 impl<T> Fragile<T> {
@@ -32,25 +57,108 @@ impl<T> Fragile<T> {
     /// fragile wrapper type ends up being send from thread to thread
     /// only the original thread can interact with the value.
     pub fn new(value: T) -> Self {
+        let home = Home::current();
+        home.reclaim();
+        Fragile {
+            value: ManuallyDrop::new(value),
+            owner: home.id(),
+            home,
+            bury: None,
+            policy: None,
+            _affinity: PhantomData,
+        }
+    }
+
+    /// Returns `true` if the thread that wrapped the value is still running.
+    #[inline]
+    pub fn owner_alive(&self) -> bool {
+        self.home.is_alive()
+    }
+
+    /// Returns `true` if the thread that wrapped the value has exited.
+    ///
+    /// An orphaned value can never be accessed again.  Dropping it on any
+    /// thread silently leaks the value.
+    #[inline]
+    pub fn is_orphaned(&self) -> bool {
+        !self.owner_alive()
+    }
+}
+
+impl<T, A: Affinity> Fragile<T, A> {
+    /// Creates a new [`Fragile`] bound to the current context of the affinity `A`.
+    ///
+    /// # Safety
+    ///
+    /// The value can be accessed on every thread the context is current on.
+    /// The caller has to ensure that this is sound for the value, which means
+    /// that it must not share non thread-safe state such as an `Rc` or data in
+    /// thread locals with anything outside of the context.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no current context.
+    #[track_caller]
+    pub unsafe fn with_affinity(value: T) -> Self {
+        let owner = match A::current() {
+            Some(owner) => owner,
+            None => panic!("cannot create fragile value outside of its affinity context."),
+        };
+        let home = Home::current();
+        home.reclaim();
         Fragile {
             value: ManuallyDrop::new(value),
-            thread_id: thread::current().id(),
+            owner,
+            home,
+            bury: None,
+            policy: None,
+            _affinity: PhantomData,
         }
     }
 
     /// Returns `true` if the access is valid.
     ///
-    /// This will be `false` if the value was sent to another thread.
+    /// This will be `false` if the value was sent to another thread, or for
+    /// other affinities, if the owning context is not current.
+    #[inline(always)]
     pub fn is_valid(&self) -> bool {
-        thread::current().id() == self.thread_id
+        A::current() == Some(self.owner)
+    }
+
+    /// Returns the [`Home`] of the thread that wrapped the value.
+    pub fn home(&self) -> &Home {
+        &self.home
+    }
+
+    /// Sets the [`ViolationPolicy`] of this value.
+    ///
+    /// This overrides the global policy for this value.
+    pub fn set_violation_policy(&mut self, policy: ViolationPolicy) {
+        self.policy = Some(policy);
     }
 
     #[inline(always)]
     #[track_caller]
     fn assert_thread(&self) {
         if !self.is_valid() {
+            self.violation(ViolationKind::Access);
             panic!("trying to access wrapped value in fragile container from incorrect thread.");
         }
+        self.home.reclaim();
+    }
+
+    /// Consults the violation policy.  Returns normally if it tolerates the violation.
+    #[cold]
+    #[track_caller]
+    pub(crate) fn violation(&self, kind: ViolationKind) {
+        let default = ViolationPolicy::Panic;
+        let info = ViolationInfo::new(
+            kind,
+            WrapperKind::Fragile,
+            any::type_name::<T>(),
+            self.home.thread_id(),
+        );
+        policy::handle(self.policy, default, info);
     }
 
     /// Consumes the `Fragile`, returning the wrapped value.
@@ -66,7 +174,8 @@ impl<T> Fragile<T> {
         let mut this = ManuallyDrop::new(self);
 
         // SAFETY: `this` is not accessed beyond this point, and because it's in a ManuallyDrop its
-        // destructor is not run.
+        // destructor is not run.  The home is the only other field that needs to be dropped.
+        drop(unsafe { ptr::read(&this.home) });
         unsafe { ManuallyDrop::take(&mut this.value) }
     }
 
@@ -74,12 +183,14 @@ impl<T> Fragile<T> {
     ///
     /// The wrapped value is returned if this is called from the same thread
     /// as the one where the original value was created, otherwise the
-    /// [`Fragile`] is returned as `Err(self)`.
-    pub fn try_into_inner(self) -> Result<T, Self> {
+    /// [`Fragile`] is returned as part of the [`IntoInnerError`].
+    #[track_caller]
+    pub fn try_into_inner(self) -> Result<T, IntoInnerError<Self>> {
         if self.is_valid() {
             Ok(self.into_inner())
         } else {
-            Err(self)
+            let err = InvalidThreadAccess::new::<T>(WrapperKind::Fragile, &self.home);
+            Err(IntoInnerError::new(self, err))
         }
     }
 
@@ -109,36 +220,161 @@ impl<T> Fragile<T> {
 
     /// Tries to immutably borrow the wrapped value.
     ///
-    /// Returns `None` if the calling thread is not the one that wrapped the value.
+    /// Returns an error if the calling thread is not the one that wrapped the value.
+    #[track_caller]
     pub fn try_get(&self) -> Result<&T, InvalidThreadAccess> {
         if self.is_valid() {
+            self.home.reclaim();
             Ok(&*self.value)
         } else {
-            Err(InvalidThreadAccess)
+            Err(InvalidThreadAccess::new::<T>(
+                WrapperKind::Fragile,
+                &self.home,
+            ))
         }
     }
 
     /// Tries to mutably borrow the wrapped value.
     ///
-    /// Returns `None` if the calling thread is not the one that wrapped the value.
+    /// Returns an error if the calling thread is not the one that wrapped the value.
+    #[track_caller]
     pub fn try_get_mut(&mut self) -> Result<&mut T, InvalidThreadAccess> {
         if self.is_valid() {
+            self.home.reclaim();
             Ok(&mut *self.value)
         } else {
-            Err(InvalidThreadAccess)
+            Err(InvalidThreadAccess::new::<T>(
+                WrapperKind::Fragile,
+                &self.home,
+            ))
         }
     }
 }
 
-impl<T> Drop for Fragile<T> {
+impl<T: 'static> Fragile<T> {
+    /// Creates a new [`Fragile`] that defers its destructor to its home.
+    ///
+    /// This works like [`new`](Self::new) but if the [`Fragile`] is dropped on
+    /// a foreign thread, the value is not dropped but handed back to the thread
+    /// that created it instead of panicking.  That thread destroys it the next
+    /// time it interacts with a wrapper of this crate, calls [`pump`](crate::pump)
+    /// or shuts down.  If the owning thread is already gone, the value is leaked.
+    pub fn new_deferred(value: T) -> Self {
+        let mut rv = Fragile::new(value);
+        rv.bury = Some(bury::<T>);
+        rv
+    }
+
+    /// Returns `true` if the value is handed back to its home when dropped
+    /// on a foreign thread.
+    pub(crate) fn is_deferred(&self) -> bool {
+        self.bury.is_some()
+    }
+
+    /// Runs `f` with the wrapped value on the thread that wrapped it.
+    ///
+    /// The [`Fragile`] is moved to its home thread where `f` is invoked the
+    /// next time that thread calls [`pump`](crate::pump).  The returned task
+    /// resolves to the [`Fragile`] together with the return value of `f`.
+    /// If this is called from the home thread, `f` is invoked immediately.
+    ///
+    /// Should the home thread shut down before `f` runs, the task resolves
+    /// to an error and the value is dropped on the home thread.
+    pub fn run_on_home<F, R>(self, f: F) -> HomeTask<(Fragile<T>, R)>
+    where
+        F: FnOnce(&mut T) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let home = self.home.clone();
+        home::run_on(&home, self, move |this| f(this.get_mut()))
+    }
+
+    /// Converts the `Fragile` into a [`Sticky`] without cloning the value.
+    ///
+    /// The value is moved into the registry of the current thread.  The
+    /// violation policy carries over.
+    ///
+    /// Fails like [`try_into_inner`](Self::try_into_inner) if called from a
+    /// different thread than the one where the original value was created.
+    /// If the thread local registry of the current thread was already
+    /// destroyed, the error carries the
+    /// [`RegistryDestroyed`](crate::AccessErrorReason::RegistryDestroyed) reason.
+    #[track_caller]
+    pub fn into_sticky(self) -> Result<Sticky<T>, IntoInnerError<Self>> {
+        let bury = self.bury;
+        let policy = self.policy;
+        match Sticky::try_insert(self.try_into_inner()?) {
+            Ok(mut rv) => {
+                if let Some(policy) = policy {
+                    rv.set_violation_policy(policy);
+                }
+                Ok(rv)
+            }
+            Err((value, err)) => {
+                let mut rv = Fragile::new(value);
+                rv.bury = bury;
+                rv.policy = policy;
+                Err(IntoInnerError::new(
+                    rv,
+                    err.set_wrapper(WrapperKind::Fragile),
+                ))
+            }
+        }
+    }
+
+    /// Hands ownership of the value over to another thread.
+    ///
+    /// The returned [`Handoff`] can be sent to other threads, exactly one of
+    /// which can [`claim`](Handoff::claim) a [`Fragile`] bound to itself.  The
+    /// violation policy and deferred destruction carry over.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from a different thread than the one where the
+    /// original value was created.
+    #[track_caller]
+    pub fn handoff<M>(self) -> Handoff<Fragile<T>>
+    where
+        T: ThreadMovable<M>,
+    {
+        let bury = self.bury;
+        let policy = self.policy;
+        let value = self.into_inner();
+        Handoff::new(Box::new(move || {
+            let mut rv = Fragile::new(value);
+            rv.bury = bury;
+            rv.policy = policy;
+            rv
+        }))
+    }
+}
+
+/// Moves a value dropped on a foreign thread into the graveyard of its home.
+unsafe fn bury<T: 'static>(home: &Home, value: &mut ManuallyDrop<T>) {
+    let value = ManuallyDrop::take(value);
+    // SAFETY: the value is only ever dropped on its home thread.
+    home.bury(Box::new(move || drop(value)));
+}
+
+impl<T, A: Affinity> Drop for Fragile<T, A> {
     #[track_caller]
     fn drop(&mut self) {
         if mem::needs_drop::<T>() {
             if self.is_valid() {
+                self.home.reclaim();
                 // SAFETY: `ManuallyDrop::drop` cannot be called after this point.
                 unsafe { ManuallyDrop::drop(&mut self.value) };
+            } else if !self.home.is_alive() {
+                // the owning thread is gone so nothing can ever observe the
+                // value again.  leaking it is the only safe option left.  A
+                // value bound to a task might still be reachable but leaking
+                // is always safe.
+            } else if let Some(bury) = self.bury {
+                // SAFETY: the value is not accessed after this point.
+                unsafe { bury(&self.home, &mut self.value) };
             } else {
-                panic!("destructor of fragile object ran on wrong thread");
+                // unless the policy panics or aborts, the value is leaked.
+                self.violation(ViolationKind::Drop);
             }
         }
     }
@@ -151,11 +387,29 @@ impl<T> From<T> for Fragile<T> {
     }
 }
 
-impl<T: Clone> Clone for Fragile<T> {
+impl<T: 'static> TryFrom<Sticky<T>> for Fragile<T> {
+    type Error = IntoInnerError<Sticky<T>>;
+
+    /// Converts with [`Sticky::into_fragile`].
+    #[track_caller]
+    fn try_from(value: Sticky<T>) -> Result<Fragile<T>, IntoInnerError<Sticky<T>>> {
+        value.into_fragile()
+    }
+}
+
+impl<T: Clone, A: Affinity> Clone for Fragile<T, A> {
     #[inline]
     #[track_caller]
-    fn clone(&self) -> Fragile<T> {
-        Fragile::new(self.get().clone())
+    fn clone(&self) -> Fragile<T, A> {
+        let value = self.get().clone();
+        Fragile {
+            value: ManuallyDrop::new(value),
+            owner: self.owner,
+            home: self.home.clone(),
+            bury: self.bury,
+            policy: self.policy,
+            _affinity: PhantomData,
+        }
     }
 }
 
@@ -166,64 +420,64 @@ impl<T: Default> Default for Fragile<T> {
     }
 }
 
-impl<T: PartialEq> PartialEq for Fragile<T> {
+impl<T: PartialEq, A: Affinity> PartialEq for Fragile<T, A> {
     #[inline]
     #[track_caller]
-    fn eq(&self, other: &Fragile<T>) -> bool {
+    fn eq(&self, other: &Fragile<T, A>) -> bool {
         *self.get() == *other.get()
     }
 }
 
-impl<T: Eq> Eq for Fragile<T> {}
+impl<T: Eq, A: Affinity> Eq for Fragile<T, A> {}
 
-impl<T: PartialOrd> PartialOrd for Fragile<T> {
+impl<T: PartialOrd, A: Affinity> PartialOrd for Fragile<T, A> {
     #[inline]
     #[track_caller]
-    fn partial_cmp(&self, other: &Fragile<T>) -> Option<cmp::Ordering> {
+    fn partial_cmp(&self, other: &Fragile<T, A>) -> Option<cmp::Ordering> {
         self.get().partial_cmp(other.get())
     }
 
     #[inline]
     #[track_caller]
-    fn lt(&self, other: &Fragile<T>) -> bool {
+    fn lt(&self, other: &Fragile<T, A>) -> bool {
         *self.get() < *other.get()
     }
 
     #[inline]
     #[track_caller]
-    fn le(&self, other: &Fragile<T>) -> bool {
+    fn le(&self, other: &Fragile<T, A>) -> bool {
         *self.get() <= *other.get()
     }
 
     #[inline]
     #[track_caller]
-    fn gt(&self, other: &Fragile<T>) -> bool {
+    fn gt(&self, other: &Fragile<T, A>) -> bool {
         *self.get() > *other.get()
     }
 
     #[inline]
     #[track_caller]
-    fn ge(&self, other: &Fragile<T>) -> bool {
+    fn ge(&self, other: &Fragile<T, A>) -> bool {
         *self.get() >= *other.get()
     }
 }
 
-impl<T: Ord> Ord for Fragile<T> {
+impl<T: Ord, A: Affinity> Ord for Fragile<T, A> {
     #[inline]
     #[track_caller]
-    fn cmp(&self, other: &Fragile<T>) -> cmp::Ordering {
+    fn cmp(&self, other: &Fragile<T, A>) -> cmp::Ordering {
         self.get().cmp(other.get())
     }
 }
 
-impl<T: fmt::Display> fmt::Display for Fragile<T> {
+impl<T: fmt::Display, A: Affinity> fmt::Display for Fragile<T, A> {
     #[track_caller]
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         fmt::Display::fmt(self.get(), f)
     }
 }
 
-impl<T: fmt::Debug> fmt::Debug for Fragile<T> {
+impl<T: fmt::Debug, A: Affinity> fmt::Debug for Fragile<T, A> {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         match self.try_get() {
             Ok(value) => f.debug_struct("Fragile").field("value", value).finish(),
@@ -243,14 +497,119 @@ impl<T: fmt::Debug> fmt::Debug for Fragile<T> {
     }
 }
 
+impl<T: Hash, A: Affinity> Hash for Fragile<T, A> {
+    #[inline]
+    #[track_caller]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.get().hash(state)
+    }
+}
+
+impl<T: Iterator, A: Affinity> Iterator for Fragile<T, A> {
+    type Item = T::Item;
+
+    #[inline]
+    #[track_caller]
+    fn next(&mut self) -> Option<T::Item> {
+        self.get_mut().next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.try_get() {
+            Ok(x) => x.size_hint(),
+            Err(_) => (0, None),
+        }
+    }
+}
+
+impl<T: DoubleEndedIterator, A: Affinity> DoubleEndedIterator for Fragile<T, A> {
+    #[inline]
+    #[track_caller]
+    fn next_back(&mut self) -> Option<T::Item> {
+        self.get_mut().next_back()
+    }
+}
+
+impl<T: ExactSizeIterator, A: Affinity> ExactSizeIterator for Fragile<T, A> {
+    #[inline]
+    #[track_caller]
+    fn len(&self) -> usize {
+        self.get().len()
+    }
+}
+
+impl<T: io::Read, A: Affinity> io::Read for Fragile<T, A> {
+    #[inline]
+    #[track_caller]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.get_mut().read(buf)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn read_vectored(&mut self, bufs: &mut [io::IoSliceMut<'_>]) -> io::Result<usize> {
+        self.get_mut().read_vectored(bufs)
+    }
+}
+
+impl<T: io::Write, A: Affinity> io::Write for Fragile<T, A> {
+    #[inline]
+    #[track_caller]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.get_mut().write(buf)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+        self.get_mut().write_vectored(bufs)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn flush(&mut self) -> io::Result<()> {
+        self.get_mut().flush()
+    }
+}
+
+impl<T: io::Seek, A: Affinity> io::Seek for Fragile<T, A> {
+    #[inline]
+    #[track_caller]
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.get_mut().seek(pos)
+    }
+}
+
+impl<T: io::BufRead, A: Affinity> io::BufRead for Fragile<T, A> {
+    #[inline]
+    #[track_caller]
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.get_mut().fill_buf()
+    }
+
+    #[inline]
+    #[track_caller]
+    fn consume(&mut self, amt: usize) {
+        self.get_mut().consume(amt)
+    }
+}
+
+impl<T: error::Error, A: Affinity> error::Error for Fragile<T, A> {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        // errors are frequently reported on other threads, so this must not panic.
+        self.try_get().ok().and_then(|x| x.source())
+    }
+}
+
 // this type is sync because access can only ever happy from the same thread
 // that created it originally.  All other threads will be able to safely
 // call some basic operations on the reference and they will fail.
-unsafe impl<T> Sync for Fragile<T> {}
+unsafe impl<T, A: Affinity> Sync for Fragile<T, A> {}
 
 // The entire point of this type is to be Send
 #[allow(clippy::non_send_fields_in_send_ty)]
-unsafe impl<T> Send for Fragile<T> {}
+unsafe impl<T, A: Affinity> Send for Fragile<T, A> {}
 
 #[test]
 fn test_basic() {
@@ -339,3 +698,279 @@ fn test_rc_sending() {
 
     thread.join().unwrap();
 }
+
+#[test]
+fn test_run_on_home() {
+    use std::rc::Rc;
+    use std::sync::mpsc::channel;
+    use std::thread;
+
+    let val = Fragile::new(Rc::new(41));
+    let (tx, rx) = channel();
+    let thread = thread::spawn(move || {
+        let task = val.run_on_home(|value| {
+            *Rc::get_mut(value).unwrap() += 1;
+            **value
+        });
+        tx.send(()).unwrap();
+        task.wait().unwrap()
+    });
+
+    rx.recv().unwrap();
+    assert_eq!(crate::pump(), 1);
+    let (val, rv) = thread.join().unwrap();
+    assert_eq!(rv, 42);
+    assert_eq!(**val.get(), 42);
+}
+
+#[test]
+fn test_run_on_home_same_thread() {
+    let val = Fragile::new(1);
+    let task = val.run_on_home(|value| *value + 1);
+    assert!(task.is_finished());
+    let (val, rv) = task.wait().unwrap();
+    assert_eq!(rv, 2);
+    assert_eq!(*val.get(), 1);
+}
+
+#[test]
+fn test_deferred_drop_elsewhere() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    let was_called = Arc::new(AtomicBool::new(false));
+    struct X(Arc<AtomicBool>);
+    impl Drop for X {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+    let val = Fragile::new_deferred(X(was_called.clone()));
+    thread::spawn(move || {
+        val.try_get().ok();
+    })
+    .join()
+    .unwrap();
+    assert!(!was_called.load(Ordering::SeqCst));
+    crate::pump();
+    assert!(was_called.load(Ordering::SeqCst));
+}
+
+#[test]
+fn test_leak_policy_drop_elsewhere() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    let was_called = Arc::new(AtomicBool::new(false));
+    struct X(Arc<AtomicBool>);
+    impl Drop for X {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+    let mut val = Fragile::new(X(was_called.clone()));
+    val.set_violation_policy(ViolationPolicy::Leak);
+    thread::spawn(move || {
+        val.try_get().ok();
+    })
+    .join()
+    .unwrap();
+    assert!(!was_called.load(Ordering::SeqCst));
+}
+
+#[test]
+fn test_callback_policy() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    fn callback(info: &ViolationInfo) {
+        assert_eq!(info.wrapper(), WrapperKind::Fragile);
+        assert_eq!(info.type_name(), any::type_name::<Vec<i32>>());
+        assert_ne!(Some(info.owner()), info.current());
+        CALLS.fetch_add(1, Ordering::SeqCst);
+    }
+    let mut val = Fragile::new(vec![1, 2, 3]);
+    val.set_violation_policy(ViolationPolicy::Callback(callback));
+    let rv = thread::spawn(move || {
+        // accessing still panics after the callback was invoked.
+        let _ = val.get();
+    })
+    .join();
+    assert!(rv.is_err());
+    // both the access and the drop of the value were reported
+    assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_violation_during_thread_shutdown() {
+    use std::cell::RefCell;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    fn callback(info: &ViolationInfo) {
+        assert_eq!(info.kind(), crate::ViolationKind::Drop);
+        CALLS.fetch_add(1, Ordering::SeqCst);
+    }
+    thread_local!(static SLOT: RefCell<Option<Fragile<Vec<u32>>>> = RefCell::new(None));
+
+    let mut val = Fragile::new(vec![42]);
+    val.set_violation_policy(ViolationPolicy::Callback(callback));
+    // the value is dropped by the destructor of a thread local of a foreign
+    // thread which must report the violation rather than panic.
+    thread::spawn(move || SLOT.with(|slot| *slot.borrow_mut() = Some(val)))
+        .join()
+        .unwrap();
+    assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_access_error() {
+    use crate::AccessErrorReason;
+    use std::thread;
+
+    let val = thread::Builder::new()
+        .name("owner".into())
+        .spawn(|| Fragile::new(true))
+        .unwrap()
+        .join()
+        .unwrap();
+    let owner = val.home().thread_id();
+
+    let err = val.try_get().unwrap_err();
+    assert_eq!(err.reason(), AccessErrorReason::OwnerExited);
+    assert_eq!(err.wrapper(), WrapperKind::Fragile);
+    assert_eq!(err.type_name(), "bool");
+    assert_eq!(err.owner(), owner);
+    assert_eq!(err.owner_name(), Some("owner"));
+    assert_eq!(err.current(), Some(thread::current().id()));
+    assert_eq!(err.location().file(), file!());
+    assert!(err.to_string().starts_with(
+        "fragile value accessed after its owning thread exited (type: bool, owner: 'owner' "
+    ));
+
+    let err = val.try_into_inner().unwrap_err();
+    assert_eq!(err.error().reason(), AccessErrorReason::OwnerExited);
+}
+
+#[test]
+fn test_access_error_wrong_thread() {
+    use crate::AccessErrorReason;
+    use std::thread;
+
+    let val = Fragile::new(true);
+    let val = thread::spawn(move || {
+        let err = val.try_get().unwrap_err();
+        assert_eq!(err.reason(), AccessErrorReason::WrongThread);
+        assert_ne!(err.current(), Some(err.owner()));
+        val.try_into_inner().unwrap_err().into_inner()
+    })
+    .join()
+    .unwrap();
+    assert!(val.try_into_inner().unwrap());
+}
+
+#[test]
+fn test_access_error_during_thread_shutdown() {
+    use crate::AccessErrorReason;
+    use std::thread;
+
+    struct Probe(Option<Fragile<bool>>);
+    impl Drop for Probe {
+        fn drop(&mut self) {
+            let val = self.0.take().unwrap();
+            let err = val.try_get().unwrap_err();
+            assert_eq!(err.reason(), AccessErrorReason::WrongThread);
+            mem::forget(val);
+        }
+    }
+    thread_local!(static PROBE: std::cell::RefCell<Probe> = std::cell::RefCell::new(Probe(None)));
+
+    let val = Fragile::new(true);
+    thread::spawn(move || PROBE.with(|probe| probe.borrow_mut().0 = Some(val)))
+        .join()
+        .unwrap();
+}
+
+#[test]
+fn test_orphaned_drop() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    let was_called = Arc::new(AtomicBool::new(false));
+    struct X(Arc<AtomicBool>);
+    impl Drop for X {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+    let was_called2 = was_called.clone();
+    let val = thread::spawn(move || {
+        let mut val = Fragile::new(X(was_called2));
+        val.set_violation_policy(ViolationPolicy::Panic);
+        assert!(val.owner_alive());
+        val
+    })
+    .join()
+    .unwrap();
+    assert!(val.is_orphaned());
+    assert!(!val.owner_alive());
+    // dropping neither panics nor runs the destructor.
+    drop(val);
+    assert!(!was_called.load(Ordering::SeqCst));
+}
+
+#[test]
+fn test_std_traits() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::io::{BufRead, Cursor, Read, Seek, SeekFrom, Write};
+    use std::thread;
+
+    let mut iter = Fragile::new(vec![1, 2, 3].into_iter());
+    assert_eq!(iter.len(), 3);
+    assert_eq!(iter.next_back(), Some(3));
+    assert_eq!(iter.collect::<Vec<_>>(), vec![1, 2]);
+
+    let mut cursor = Fragile::new(Cursor::new(Vec::new()));
+    cursor.write_all(b"hello\nworld").unwrap();
+    cursor.seek(SeekFrom::Start(0)).unwrap();
+    let mut line = String::new();
+    cursor.read_line(&mut line).unwrap();
+    assert_eq!(line, "hello\n");
+    let mut rest = String::new();
+    cursor.read_to_string(&mut rest).unwrap();
+    assert_eq!(rest, "world");
+
+    let mut a = DefaultHasher::new();
+    let mut b = DefaultHasher::new();
+    Fragile::new("hash").hash(&mut a);
+    "hash".hash(&mut b);
+    assert_eq!(a.finish(), b.finish());
+
+    #[derive(Debug)]
+    struct Outer(std::fmt::Error);
+    impl fmt::Display for Outer {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("outer")
+        }
+    }
+    impl error::Error for Outer {
+        fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+    let err = Fragile::new(Outer(std::fmt::Error));
+    assert!(error::Error::source(&err).is_some());
+    thread::spawn(move || assert!(error::Error::source(&err).is_none()))
+        .join()
+        .unwrap();
+}
+
+#[test]
+fn test_into_inner_releases_home() {
+    let home = Home::current();
+    let before = home.strong_count();
+    let val = Fragile::new(vec![1, 2, 3]);
+    assert_eq!(home.strong_count(), before + 1);
+    assert_eq!(val.into_inner(), vec![1, 2, 3]);
+    assert_eq!(home.strong_count(), before);
+}
@@ -2,13 +2,18 @@ use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
-use crate::{stack_token, Fragile, SemiSticky, Sticky};
+use crate::policy::ViolationKind;
+use crate::{stack_token, Affinity, Fragile, SemiSticky, Sticky, Storage};
 
-impl<F: Future> Future for Fragile<F> {
+impl<F: Future, A: Affinity> Future for Fragile<F, A> {
     type Output = F::Output;
 
     #[track_caller]
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if !self.is_valid() {
+            self.violation(ViolationKind::Poll);
+            return Poll::Pending;
+        }
         unsafe { self.map_unchecked_mut(|s| s.get_mut()) }.poll(cx)
     }
 }
@@ -18,18 +23,27 @@ impl<F: Future> Future for Sticky<F> {
 
     #[track_caller]
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        stack_token!(tok);
-        unsafe { Pin::new_unchecked(Sticky::get_mut(&mut self, tok)) }.poll(cx)
+        if !self.is_valid() {
+            self.violation(ViolationKind::Poll);
+            return Poll::Pending;
+        }
+        // SAFETY: the value is boxed in the registry and never moves.
+        Sticky::with_mut(&mut self, |x| unsafe { Pin::new_unchecked(x) }.poll(cx))
     }
 }
 
-impl<F: Future> Future for SemiSticky<F> {
+impl<F: Future, S: Storage<F>> Future for SemiSticky<F, S> {
     type Output = F::Output;
 
     #[track_caller]
-    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        stack_token!(tok);
-        unsafe { Pin::new_unchecked(SemiSticky::get_mut(&mut self, tok)) }.poll(cx)
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if !self.is_valid() {
+            self.violation(ViolationKind::Poll);
+            return Poll::Pending;
+        }
+        // SAFETY: the future is never moved out of a pinned wrapper.
+        let this = unsafe { self.get_unchecked_mut() };
+        this.with_mut(|x| unsafe { Pin::new_unchecked(x) }.poll(cx))
     }
 }
 
@@ -38,11 +52,15 @@ mod stream {
     use super::*;
     use futures_core::Stream;
 
-    impl<S: Stream> Stream for Fragile<S> {
+    impl<S: Stream, A: Affinity> Stream for Fragile<S, A> {
         type Item = S::Item;
 
         #[track_caller]
         fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            if !self.is_valid() {
+                self.violation(ViolationKind::Poll);
+                return Poll::Pending;
+            }
             unsafe { self.map_unchecked_mut(|s| s.get_mut()) }.poll_next(cx)
         }
 
@@ -60,8 +78,14 @@ mod stream {
 
         #[track_caller]
         fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-            stack_token!(tok);
-            unsafe { Pin::new_unchecked(Sticky::get_mut(&mut self, tok)) }.poll_next(cx)
+            if !self.is_valid() {
+                self.violation(ViolationKind::Poll);
+                return Poll::Pending;
+            }
+            // SAFETY: the value is boxed in the registry and never moves.
+            Sticky::with_mut(&mut self, |x| {
+                unsafe { Pin::new_unchecked(x) }.poll_next(cx)
+            })
         }
 
         #[inline]
@@ -74,13 +98,18 @@ mod stream {
         }
     }
 
-    impl<S: Stream> Stream for SemiSticky<S> {
-        type Item = S::Item;
+    impl<T: Stream, S: Storage<T>> Stream for SemiSticky<T, S> {
+        type Item = T::Item;
 
         #[track_caller]
-        fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-            stack_token!(tok);
-            unsafe { Pin::new_unchecked(SemiSticky::get_mut(&mut self, tok)) }.poll_next(cx)
+        fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            if !self.is_valid() {
+                self.violation(ViolationKind::Poll);
+                return Poll::Pending;
+            }
+            // SAFETY: the stream is never moved out of a pinned wrapper.
+            let this = unsafe { self.get_unchecked_mut() };
+            this.with_mut(|x| unsafe { Pin::new_unchecked(x) }.poll_next(cx))
         }
 
         #[inline]
@@ -138,6 +167,467 @@ mod stream {
     }
 }
 
+#[cfg(feature = "stream")]
+mod fused {
+    use super::*;
+    use futures_core::future::FusedFuture;
+    use futures_core::stream::FusedStream;
+
+    impl<F: FusedFuture, A: Affinity> FusedFuture for Fragile<F, A> {
+        fn is_terminated(&self) -> bool {
+            self.try_get().map_or(false, |x| x.is_terminated())
+        }
+    }
+
+    impl<F: FusedFuture> FusedFuture for Sticky<F> {
+        fn is_terminated(&self) -> bool {
+            stack_token!(tok);
+            Sticky::try_get(self, tok).map_or(false, |x| x.is_terminated())
+        }
+    }
+
+    impl<F: FusedFuture, S: Storage<F>> FusedFuture for SemiSticky<F, S> {
+        fn is_terminated(&self) -> bool {
+            stack_token!(tok);
+            SemiSticky::try_get(self, tok).map_or(false, |x| x.is_terminated())
+        }
+    }
+
+    impl<S: FusedStream, A: Affinity> FusedStream for Fragile<S, A> {
+        fn is_terminated(&self) -> bool {
+            self.try_get().map_or(false, |x| x.is_terminated())
+        }
+    }
+
+    impl<S: FusedStream> FusedStream for Sticky<S> {
+        fn is_terminated(&self) -> bool {
+            stack_token!(tok);
+            Sticky::try_get(self, tok).map_or(false, |x| x.is_terminated())
+        }
+    }
+
+    impl<T: FusedStream, S: Storage<T>> FusedStream for SemiSticky<T, S> {
+        fn is_terminated(&self) -> bool {
+            stack_token!(tok);
+            SemiSticky::try_get(self, tok).map_or(false, |x| x.is_terminated())
+        }
+    }
+
+    #[test]
+    fn test_fused() {
+        use futures_executor as executor;
+        use futures_util::{future, stream, StreamExt};
+
+        let mut w = Fragile::new(stream::once(future::ready(42)));
+        assert!(!FusedStream::is_terminated(&w));
+        assert_eq!(executor::block_on(w.next()), Some(42));
+        assert_eq!(executor::block_on(w.next()), None);
+        assert!(FusedStream::is_terminated(&w));
+
+        let mut w = Sticky::new(future::ready(42));
+        assert!(!FusedFuture::is_terminated(&w));
+        assert_eq!(executor::block_on(&mut w), 42);
+        assert!(FusedFuture::is_terminated(&w));
+
+        // from a foreign thread the value is never terminated
+        let w = SemiSticky::new(stream::empty::<()>());
+        assert!(FusedStream::is_terminated(&w));
+        std::thread::spawn(move || assert!(!FusedStream::is_terminated(&w)))
+            .join()
+            .unwrap();
+    }
+}
+
+/// Forwarding impls of the `futures_sink` and `futures_io` traits.
+#[cfg(any(feature = "sink", feature = "io"))]
+mod forward {
+    use super::*;
+
+    /// Pinned access to the value of a wrapper for the forwarding impls below.
+    trait Pinned {
+        type Target;
+
+        /// Returns `false` after consulting the violation policy if the value
+        /// cannot be polled from the current thread.
+        fn check_poll(&self) -> bool;
+
+        /// Invokes `f` with the pinned value.  Panics on the wrong thread.
+        fn with_pinned<R, F: FnOnce(Pin<&mut Self::Target>) -> R>(self: Pin<&mut Self>, f: F) -> R;
+
+        /// Polls the value with `f` or returns `Poll::Pending` on the wrong thread.
+        #[track_caller]
+        fn poll_with<R, F>(self: Pin<&mut Self>, f: F) -> Poll<R>
+        where
+            F: FnOnce(Pin<&mut Self::Target>) -> Poll<R>,
+        {
+            if self.check_poll() {
+                self.with_pinned(f)
+            } else {
+                Poll::Pending
+            }
+        }
+    }
+
+    impl<T, A: Affinity> Pinned for Fragile<T, A> {
+        type Target = T;
+
+        #[track_caller]
+        fn check_poll(&self) -> bool {
+            let rv = self.is_valid();
+            if !rv {
+                self.violation(ViolationKind::Poll);
+            }
+            rv
+        }
+
+        #[track_caller]
+        fn with_pinned<R, F: FnOnce(Pin<&mut T>) -> R>(self: Pin<&mut Self>, f: F) -> R {
+            // SAFETY: the value is stored inline and never moved out of a pinned wrapper.
+            f(unsafe { self.map_unchecked_mut(|s| s.get_mut()) })
+        }
+    }
+
+    impl<T> Pinned for Sticky<T> {
+        type Target = T;
+
+        #[track_caller]
+        fn check_poll(&self) -> bool {
+            let rv = self.is_valid();
+            if !rv {
+                self.violation(ViolationKind::Poll);
+            }
+            rv
+        }
+
+        #[track_caller]
+        fn with_pinned<R, F: FnOnce(Pin<&mut T>) -> R>(self: Pin<&mut Self>, f: F) -> R {
+            // SAFETY: the value is boxed in the registry and never moves.
+            let this = unsafe { self.get_unchecked_mut() };
+            this.with_mut(|x| f(unsafe { Pin::new_unchecked(x) }))
+        }
+    }
+
+    impl<T, S: Storage<T>> Pinned for SemiSticky<T, S> {
+        type Target = T;
+
+        #[track_caller]
+        fn check_poll(&self) -> bool {
+            let rv = self.is_valid();
+            if !rv {
+                self.violation(ViolationKind::Poll);
+            }
+            rv
+        }
+
+        #[track_caller]
+        fn with_pinned<R, F: FnOnce(Pin<&mut T>) -> R>(self: Pin<&mut Self>, f: F) -> R {
+            // SAFETY: the value is either stored inline or boxed in the
+            // registry and never moved out of a pinned wrapper.
+            let this = unsafe { self.get_unchecked_mut() };
+            this.with_mut(|x| f(unsafe { Pin::new_unchecked(x) }))
+        }
+    }
+
+    /// Implements a forwarding trait for all three wrappers.
+    #[cfg(feature = "io")]
+    macro_rules! forward_impls {
+        ($(impl[$($gen:tt)*] $trait:ty { $($body:tt)* })*) => {$(
+            impl<T: $($gen)*, A: Affinity> $trait for Fragile<T, A> { $($body)* }
+            impl<T: $($gen)*> $trait for Sticky<T> { $($body)* }
+            impl<T: $($gen)*, S: Storage<T>> $trait for SemiSticky<T, S> { $($body)* }
+        )*};
+    }
+
+    #[cfg(feature = "sink")]
+    mod sink {
+        use super::*;
+        use futures_sink::Sink;
+
+        /// Forwards the methods of `Sink`.
+        macro_rules! forward_sink {
+            () => {
+                #[track_caller]
+                fn poll_ready(
+                    self: Pin<&mut Self>,
+                    cx: &mut Context<'_>,
+                ) -> Poll<Result<(), Self::Error>> {
+                    self.poll_with(|x| x.poll_ready(cx))
+                }
+
+                #[track_caller]
+                fn start_send(self: Pin<&mut Self>, item: Item) -> Result<(), Self::Error> {
+                    self.with_pinned(|x| x.start_send(item))
+                }
+
+                #[track_caller]
+                fn poll_flush(
+                    self: Pin<&mut Self>,
+                    cx: &mut Context<'_>,
+                ) -> Poll<Result<(), Self::Error>> {
+                    self.poll_with(|x| x.poll_flush(cx))
+                }
+
+                #[track_caller]
+                fn poll_close(
+                    self: Pin<&mut Self>,
+                    cx: &mut Context<'_>,
+                ) -> Poll<Result<(), Self::Error>> {
+                    self.poll_with(|x| x.poll_close(cx))
+                }
+            };
+        }
+
+        impl<T: Sink<Item>, Item, A: Affinity> Sink<Item> for Fragile<T, A> {
+            type Error = T::Error;
+            forward_sink!();
+        }
+
+        impl<T: Sink<Item>, Item> Sink<Item> for Sticky<T> {
+            type Error = T::Error;
+            forward_sink!();
+        }
+
+        impl<T: Sink<Item>, Item, S: Storage<T>> Sink<Item> for SemiSticky<T, S> {
+            type Error = T::Error;
+            forward_sink!();
+        }
+
+        #[test]
+        fn test_sink() {
+            use futures_util::task::noop_waker;
+            use std::thread;
+
+            let waker = noop_waker();
+            let mut cx = Context::from_waker(&waker);
+            let mut w = Sticky::new(Vec::new());
+            assert!(Pin::new(&mut w).poll_ready(&mut cx).is_ready());
+            Pin::new(&mut w).start_send(42).unwrap();
+            assert!(Pin::new(&mut w).poll_flush(&mut cx).is_ready());
+            stack_token!(tok);
+            assert_eq!(w.get(tok), &[42]);
+
+            let mut w = Fragile::new(Vec::<i32>::new());
+            w.set_violation_policy(crate::ViolationPolicy::Leak);
+            thread::spawn(move || {
+                let waker = noop_waker();
+                let mut cx = Context::from_waker(&waker);
+                assert!(Pin::new(&mut w).poll_ready(&mut cx).is_pending());
+            })
+            .join()
+            .unwrap();
+        }
+    }
+
+    #[cfg(feature = "io")]
+    mod io {
+        use super::*;
+        use crate::StackToken;
+        use futures_io::{
+            AsyncBufRead, AsyncRead, AsyncSeek, AsyncWrite, IoSlice, IoSliceMut, SeekFrom,
+        };
+        use std::io::Result;
+
+        forward_impls! {
+            impl[AsyncRead] AsyncRead {
+                #[track_caller]
+                fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<Result<usize>> {
+                    self.poll_with(|x| x.poll_read(cx, buf))
+                }
+
+                #[track_caller]
+                fn poll_read_vectored(
+                    self: Pin<&mut Self>,
+                    cx: &mut Context<'_>,
+                    bufs: &mut [IoSliceMut<'_>],
+                ) -> Poll<Result<usize>> {
+                    self.poll_with(|x| x.poll_read_vectored(cx, bufs))
+                }
+            }
+
+            impl[AsyncWrite] AsyncWrite {
+                #[track_caller]
+                fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<Result<usize>> {
+                    self.poll_with(|x| x.poll_write(cx, buf))
+                }
+
+                #[track_caller]
+                fn poll_write_vectored(
+                    self: Pin<&mut Self>,
+                    cx: &mut Context<'_>,
+                    bufs: &[IoSlice<'_>],
+                ) -> Poll<Result<usize>> {
+                    self.poll_with(|x| x.poll_write_vectored(cx, bufs))
+                }
+
+                #[track_caller]
+                fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+                    self.poll_with(|x| x.poll_flush(cx))
+                }
+
+                #[track_caller]
+                fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+                    self.poll_with(|x| x.poll_close(cx))
+                }
+            }
+
+            impl[AsyncSeek] AsyncSeek {
+                #[track_caller]
+                fn poll_seek(self: Pin<&mut Self>, cx: &mut Context<'_>, pos: SeekFrom) -> Poll<Result<u64>> {
+                    self.poll_with(|x| x.poll_seek(cx, pos))
+                }
+            }
+        }
+
+        // the buffer borrowed from a `Sticky` could outlive the registry of
+        // its thread, so only a `Fragile` which stores the value inline can
+        // forward `AsyncBufRead`.  The others offer `poll_fill_buf_with`.
+        impl<T: AsyncBufRead, A: Affinity> AsyncBufRead for Fragile<T, A> {
+            #[track_caller]
+            fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<&[u8]>> {
+                if !self.check_poll() {
+                    return Poll::Pending;
+                }
+                // SAFETY: the value is stored inline and never moved out of a pinned wrapper.
+                unsafe { self.map_unchecked_mut(|s| s.get_mut()) }.poll_fill_buf(cx)
+            }
+
+            #[track_caller]
+            fn consume(self: Pin<&mut Self>, amt: usize) {
+                self.with_pinned(|x| x.consume(amt))
+            }
+        }
+
+        impl<T: AsyncBufRead> Sticky<T> {
+            /// Polls the buffer of the wrapped reader.
+            ///
+            /// A [`Sticky`] cannot implement `AsyncBufRead` because the buffer
+            /// could outlive the registry of the owning thread.  This is the
+            /// counterpart of `poll_fill_buf` which ties the buffer to a
+            /// [`StackToken`] instead.  Use [`consume_pinned`](Self::consume_pinned)
+            /// to consume from the buffer.
+            ///
+            /// # Panics
+            ///
+            /// Panics if called from a different thread than the one where the
+            /// original value was created or if
+            /// [`StickyWeak`](crate::StickyWeak) handles to the value exist as
+            /// the buffer borrows it.
+            #[track_caller]
+            pub fn poll_fill_buf_with<'stack>(
+                self: Pin<&'stack mut Self>,
+                cx: &mut Context<'_>,
+                _proof: &'stack StackToken,
+            ) -> Poll<Result<&'stack [u8]>> {
+                if !self.is_valid() {
+                    self.violation(ViolationKind::Poll);
+                    return Poll::Pending;
+                }
+                // SAFETY: the reader is never moved out of a pinned wrapper.
+                let this = unsafe { self.get_unchecked_mut() };
+                unsafe { Pin::new_unchecked(Sticky::get_mut(this, _proof)) }.poll_fill_buf(cx)
+            }
+
+            /// Consumes `amt` bytes from the buffer of the wrapped reader.
+            ///
+            /// # Panics
+            ///
+            /// Panics if called from a different thread than the one where the
+            /// original value was created.
+            #[track_caller]
+            pub fn consume_pinned(self: Pin<&mut Self>, amt: usize) {
+                // SAFETY: the reader is never moved out of a pinned wrapper.
+                let this = unsafe { self.get_unchecked_mut() };
+                this.with_mut(|x| unsafe { Pin::new_unchecked(x) }.consume(amt))
+            }
+        }
+
+        impl<T: AsyncBufRead, S: Storage<T>> SemiSticky<T, S> {
+            /// Polls the buffer of the wrapped reader.
+            ///
+            /// See [`Sticky::poll_fill_buf_with`].
+            ///
+            /// # Panics
+            ///
+            /// Panics if called from a different thread than the one where the
+            /// original value was created or if
+            /// [`StickyWeak`](crate::StickyWeak) handles to the value exist as
+            /// the buffer borrows it.
+            #[track_caller]
+            pub fn poll_fill_buf_with<'stack>(
+                self: Pin<&'stack mut Self>,
+                cx: &mut Context<'_>,
+                _proof: &'stack StackToken,
+            ) -> Poll<Result<&'stack [u8]>> {
+                if !self.is_valid() {
+                    self.violation(ViolationKind::Poll);
+                    return Poll::Pending;
+                }
+                // SAFETY: the reader is never moved out of a pinned wrapper.
+                let this = unsafe { self.get_unchecked_mut() };
+                unsafe { Pin::new_unchecked(SemiSticky::get_mut(this, _proof)) }.poll_fill_buf(cx)
+            }
+
+            /// Consumes `amt` bytes from the buffer of the wrapped reader.
+            ///
+            /// # Panics
+            ///
+            /// Panics if called from a different thread than the one where the
+            /// original value was created.
+            #[track_caller]
+            pub fn consume_pinned(self: Pin<&mut Self>, amt: usize) {
+                // SAFETY: the reader is never moved out of a pinned wrapper.
+                let this = unsafe { self.get_unchecked_mut() };
+                this.with_mut(|x| unsafe { Pin::new_unchecked(x) }.consume(amt))
+            }
+        }
+
+        #[test]
+        fn test_io() {
+            use futures_util::task::noop_waker;
+
+            let waker = noop_waker();
+            let mut cx = Context::from_waker(&waker);
+
+            let mut w = Sticky::new(Vec::new());
+            let rv = Pin::new(&mut w).poll_write(&mut cx, b"hello world");
+            assert!(matches!(rv, Poll::Ready(Ok(11))));
+            assert!(Pin::new(&mut w).poll_close(&mut cx).is_ready());
+
+            let mut w = SemiSticky::new(&b"hello world"[..]);
+            let mut buf = [0; 5];
+            let rv = Pin::new(&mut w).poll_read(&mut cx, &mut buf);
+            assert!(matches!(rv, Poll::Ready(Ok(5))));
+            assert_eq!(&buf, b"hello");
+
+            let mut w = Fragile::new(&b"abc"[..]);
+            match Pin::new(&mut w).poll_fill_buf(&mut cx) {
+                Poll::Ready(Ok(buf)) => assert_eq!(buf, b"abc"),
+                _ => panic!("expected a buffer"),
+            }
+            Pin::new(&mut w).consume(2);
+            match Pin::new(&mut w).poll_fill_buf(&mut cx) {
+                Poll::Ready(Ok(buf)) => assert_eq!(buf, b"c"),
+                _ => panic!("expected a buffer"),
+            }
+
+            stack_token!(tok);
+            let mut w = Sticky::new(&b"abc"[..]);
+            Pin::new(&mut w).consume_pinned(1);
+            match Pin::new(&mut w).poll_fill_buf_with(&mut cx, tok) {
+                Poll::Ready(Ok(buf)) => assert_eq!(buf, b"bc"),
+                _ => panic!("expected a buffer"),
+            }
+
+            let mut w = SemiSticky::new(&b"abc"[..]);
+            Pin::new(&mut w).consume_pinned(2);
+            match Pin::new(&mut w).poll_fill_buf_with(&mut cx, tok) {
+                Poll::Ready(Ok(buf)) => assert_eq!(buf, b"c"),
+                _ => panic!("expected a buffer"),
+            }
+        }
+    }
+}
+
 #[test]
 fn test_future() {
     use futures_executor as executor;
@@ -180,3 +670,18 @@ fn test_future_panic() {
     let t = std::thread::spawn(move || executor::block_on(w));
     assert!(t.join().is_err());
 }
+
+#[test]
+fn test_future_leak_policy() {
+    use crate::ViolationPolicy;
+    use futures_util::future;
+
+    let mut w = Fragile::new(future::ready(42));
+    w.set_violation_policy(ViolationPolicy::Leak);
+    let t = std::thread::spawn(move || {
+        let waker = futures_util::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        Pin::new(&mut w).poll(&mut cx).is_pending()
+    });
+    assert!(t.join().unwrap());
+}
@@ -0,0 +1,189 @@
+use std::fmt;
+use std::sync::{Mutex, MutexGuard};
+
+mod private {
+    /// Marks the implementation of [`ThreadMovable`](super::ThreadMovable)
+    /// for `Send` types.
+    pub enum ViaSend {}
+}
+
+/// Marks types that can be moved to another thread and used there.
+///
+/// Types that are `Send` implement this trait automatically.  It can also be
+/// implemented for types that are not `Send` but can be handed over as a whole
+/// to another thread, for instance because they only hold an `Rc` that is
+/// never shared, or because they are only `!Send` for API hygiene.  Such
+/// values can then be passed between threads with [`Fragile::handoff`](crate::Fragile::handoff)
+/// and [`Sticky::handoff`](crate::Sticky::handoff).
+///
+/// The type parameter only exists to keep the implementation for `Send` types
+/// apart from manual implementations.  Generic code has to be generic over it
+/// as well to accept both kinds of types.  Implementing the trait for a type
+/// that is already `Send` is unnecessary and makes the parameter ambiguous,
+/// in which case calls have to pick the manual implementation:
+///
+/// ```
+/// use fragile::{Fragile, Handoff, ThreadMovable};
+///
+/// // accepts `Send` types as well as manual implementations.
+/// fn pass<T: 'static, M>(value: Fragile<T>) -> Handoff<Fragile<T>>
+/// where
+///     T: ThreadMovable<M>,
+/// {
+///     value.handoff()
+/// }
+///
+/// struct Counter(u32);
+/// // not needed as `Counter` is `Send`.
+/// unsafe impl ThreadMovable for Counter {}
+///
+/// let handoff = Fragile::new(Counter(42)).handoff::<()>();
+/// # drop(handoff);
+/// # drop(pass(Fragile::new(42)));
+/// ```
+///
+/// # Safety
+///
+/// Implementors have to ensure that a value of the type can be moved to
+/// another thread, used and dropped there.  In particular it must not share
+/// non thread-safe state such as an `Rc` or data in thread locals with
+/// anything that stays behind on the original thread.
+///
+/// ```
+/// use std::rc::Rc;
+/// use fragile::ThreadMovable;
+///
+/// struct Job {
+///     // the `Rc` is never cloned.
+///     data: Rc<Vec<u8>>,
+/// }
+///
+/// unsafe impl ThreadMovable for Job {}
+/// ```
+pub unsafe trait ThreadMovable<Marker = ()> {}
+
+unsafe impl<T: Send + ?Sized> ThreadMovable<private::ViaSend> for T {}
+
+/// Ownership of a wrapped value in transit between two threads.
+///
+/// A [`Handoff`] is created by [`Fragile::handoff`](crate::Fragile::handoff)
+/// or [`Sticky::handoff`](crate::Sticky::handoff) on the thread that owns the
+/// value.  It can be sent and shared between threads and exactly one of them
+/// can [`claim`](Self::claim) the wrapper which is then bound to that thread.
+/// If the [`Handoff`] is dropped before it was claimed, the value is dropped
+/// on the thread that drops it.
+///
+/// ```
+/// use std::cell::Cell;
+/// use std::thread;
+/// use fragile::Fragile;
+///
+/// let val = Fragile::new(Cell::new(21));
+/// let handoff = val.handoff();
+/// thread::spawn(move || {
+///     let val = handoff.claim().unwrap();
+///     val.get().set(val.get().get() * 2);
+///     assert_eq!(val.into_inner().get(), 42);
+/// })
+/// .join()
+/// .unwrap();
+/// ```
+pub struct Handoff<W> {
+    // `None` once claimed.
+    rebind: Mutex<Option<Box<dyn FnOnce() -> W>>>,
+}
+
+// SAFETY: handoffs are only created for values that are `ThreadMovable` and
+// the closure is invoked or dropped at most once.
+unsafe impl<W> Send for Handoff<W> {}
+unsafe impl<W> Sync for Handoff<W> {}
+
+impl<W> Handoff<W> {
+    /// Creates a handoff that invokes `rebind` on the claiming thread.
+    pub(crate) fn new(rebind: Box<dyn FnOnce() -> W>) -> Handoff<W> {
+        Handoff {
+            rebind: Mutex::new(Some(rebind)),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<Box<dyn FnOnce() -> W>>> {
+        // no foreign code runs while the lock is held
+        match self.rebind.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Claims the wrapper for the current thread.
+    ///
+    /// Returns `None` if another thread claimed it already.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a [`Sticky`](crate::Sticky) is claimed after the thread local
+    /// registry of the current thread was destroyed.
+    #[track_caller]
+    pub fn claim(&self) -> Option<W> {
+        let rebind = self.lock().take()?;
+        Some(rebind())
+    }
+
+    /// Returns `true` if the wrapper was claimed.
+    pub fn is_claimed(&self) -> bool {
+        self.lock().is_none()
+    }
+}
+
+impl<W> fmt::Debug for Handoff<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Handoff")
+            .field("claimed", &self.is_claimed())
+            .finish()
+    }
+}
+
+#[test]
+fn test_claim_once() {
+    use crate::Fragile;
+    use std::rc::Rc;
+    use std::sync::Arc;
+    use std::thread;
+
+    struct Job(Rc<u32>);
+    unsafe impl ThreadMovable for Job {}
+
+    let handoff = Arc::new(Fragile::new(Job(Rc::new(42))).handoff());
+    let workers = (0..4)
+        .map(|_| {
+            let handoff = handoff.clone();
+            thread::spawn(move || handoff.claim().map(|job| *job.into_inner().0))
+        })
+        .collect::<Vec<_>>();
+    let claimed = workers
+        .into_iter()
+        .filter_map(|x| x.join().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(claimed, vec![42]);
+    assert!(handoff.is_claimed());
+    assert!(handoff.claim().is_none());
+}
+
+#[test]
+fn test_drop_unclaimed() {
+    use crate::Sticky;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    struct X(Arc<AtomicBool>);
+    impl Drop for X {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    let dropped = Arc::new(AtomicBool::new(false));
+    let handoff = Sticky::new(X(dropped.clone())).handoff();
+    thread::spawn(move || drop(handoff)).join().unwrap();
+    assert!(dropped.load(Ordering::SeqCst));
+}
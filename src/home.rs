@@ -0,0 +1,588 @@
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::task::Waker;
+use std::thread::{self, Thread, ThreadId};
+
+use crate::errors::TaskAbandoned;
+use crate::thread_id;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A handle to the thread that owns a [`Fragile`](crate::Fragile) or
+/// [`Sticky`](crate::Sticky).
+///
+/// Every thread has exactly one home.  Other threads can use it to queue
+/// closures that are executed on the owning thread the next time that thread
+/// calls [`pump`](crate::pump) or [`Home::run_pending`].  The handle itself is
+/// `Send` and `Sync` and can be freely cloned.
+///
+/// Once the owning thread shuts down the home is closed and all work that is
+/// still queued (or is queued afterwards) is abandoned.
+#[derive(Clone)]
+pub struct Home {
+    inner: Arc<HomeInner>,
+}
+
+struct HomeInner {
+    id: NonZeroUsize,
+    thread: Thread,
+    thread_id: ThreadId,
+    thread_name: Option<String>,
+    queue: Mutex<HomeQueue>,
+    // lock-free stack of values that were dropped on foreign threads and
+    // that need to be destroyed on the home thread.
+    graveyard: AtomicPtr<Grave>,
+    // the number of graves that are waiting to be reclaimed.
+    buried: AtomicUsize,
+}
+
+/// A value buried in the graveyard of a home.
+///
+/// The closure is not `Send` as it typically owns a non-`Send` value.  It is
+/// only ever invoked on the home thread, otherwise it is leaked.
+struct Grave {
+    next: *mut Grave,
+    reclaim: Box<dyn FnOnce()>,
+}
+
+/// Marks the graveyard of a home whose thread shut down.
+const CLOSED: *mut Grave = 1 as *mut Grave;
+
+struct HomeQueue {
+    jobs: VecDeque<Job>,
+    closed: bool,
+}
+
+struct HomeGuard(Home);
+
+impl Drop for HomeGuard {
+    fn drop(&mut self) {
+        // buried values are destroyed one last time, afterwards anything
+        // dropped on a foreign thread is leaked.
+        self.0.close_graveyard();
+
+        // pending jobs are dropped here rather than executed as other thread
+        // locals they might depend on could already be gone.  Because this
+        // happens on the home thread, values captured by the jobs are dropped
+        // on the thread that owns them.
+        let jobs = {
+            let mut queue = self.0.lock_queue();
+            queue.closed = true;
+            std::mem::take(&mut queue.jobs)
+        };
+        drop(jobs);
+    }
+}
+
+thread_local!(static HOME: HomeGuard = {
+    HAS_HOME.try_with(|x| x.set(true)).ok();
+    HomeGuard(Home::new(false))
+});
+// set once the home of the current thread was created.
+thread_local!(static HAS_HOME: Cell<bool> = Cell::new(false));
+
+impl Home {
+    fn new(closed: bool) -> Home {
+        let thread = thread::current();
+        thread_id::remember_current(thread.id());
+        Home {
+            inner: Arc::new(HomeInner {
+                id: thread_id::try_get().unwrap_or_else(thread_id::next),
+                thread_id: thread.id(),
+                thread_name: thread.name().map(|x| x.to_string()),
+                thread,
+                queue: Mutex::new(HomeQueue {
+                    jobs: VecDeque::new(),
+                    closed,
+                }),
+                graveyard: AtomicPtr::new(if closed { CLOSED } else { ptr::null_mut() }),
+                buried: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// Returns the home of the current thread.
+    ///
+    /// If this is invoked while the thread is shutting down a home is returned
+    /// that is already closed.
+    pub fn current() -> Home {
+        HOME.try_with(|guard| guard.0.clone())
+            .unwrap_or_else(|_| Home::new(true))
+    }
+
+    /// Returns the ID of the owning thread.
+    pub fn thread_id(&self) -> ThreadId {
+        self.inner.thread_id
+    }
+
+    /// Returns the name of the owning thread if it has one.
+    pub fn thread_name(&self) -> Option<&str> {
+        self.inner.thread_name.as_deref()
+    }
+
+    /// Returns `true` if the owning thread has not shut down yet.
+    ///
+    /// A home is considered dead as soon as the owning thread starts tearing
+    /// down its thread locals.  Once this returns `false` it never returns
+    /// `true` again.
+    #[inline]
+    pub fn is_alive(&self) -> bool {
+        self.inner.graveyard.load(Ordering::Acquire) != CLOSED
+    }
+
+    /// Returns `true` if the current thread is the owning thread.
+    #[inline]
+    pub fn is_current(&self) -> bool {
+        thread_id::is_current(self.inner.id, self)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn strong_count(&self) -> usize {
+        Arc::strong_count(&self.inner)
+    }
+
+    /// Returns the cached ID of the owning thread.
+    #[inline(always)]
+    pub(crate) fn id(&self) -> NonZeroUsize {
+        self.inner.id
+    }
+
+    /// Queues a closure to be run on the owning thread.
+    ///
+    /// The closure is always queued, even if this is invoked from the owning
+    /// thread.  If the owning thread is parked, it is unparked so that it can
+    /// pump its home.  The returned [`HomeTask`] can be used to wait for the result.
+    /// If the owning thread has already shut down, the closure is dropped
+    /// right away and the task reports [`TaskAbandoned`].
+    pub fn spawn<F, R>(&self, f: F) -> HomeTask<R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (task, completer) = HomeTask::new(self.clone());
+        self.push(Box::new(move || completer.complete(f())));
+        task
+    }
+
+    /// Runs all closures that are currently queued for this home.
+    ///
+    /// Closures that are queued while this runs are left for the next
+    /// invocation.  Returns the number of closures that were executed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from a thread other than the owning thread.
+    #[track_caller]
+    pub fn run_pending(&self) -> usize {
+        if !self.is_current() {
+            panic!("trying to run pending work of a home from incorrect thread.");
+        }
+
+        // jobs are taken one at a time so that the ones that are left are
+        // kept queued if one of them panics.
+        let pending = self.lock_queue().jobs.len();
+        let mut ran = 0;
+        while ran < pending {
+            let job = match self.lock_queue().jobs.pop_front() {
+                Some(job) => job,
+                None => break,
+            };
+            job();
+            ran += 1;
+        }
+        ran
+    }
+
+    pub(crate) fn push(&self, job: Job) {
+        let mut queue = self.lock_queue();
+        if queue.closed {
+            // release the lock before the job (and everything it captures)
+            // is dropped.
+            drop(queue);
+            drop(job);
+        } else {
+            queue.jobs.push_back(job);
+            drop(queue);
+            self.unpark();
+        }
+    }
+
+    /// Unparks the owning thread.
+    pub(crate) fn unpark(&self) {
+        self.inner.thread.unpark();
+    }
+
+    /// Buries a value dropped on a foreign thread.
+    ///
+    /// `reclaim` is invoked on the home thread the next time it interacts
+    /// with this crate.  If the home thread already shut down, `reclaim` (and
+    /// everything it captured) is leaked instead.
+    ///
+    /// # Safety
+    ///
+    /// `reclaim` must be safe to invoke on the home thread at any later point.
+    pub(crate) unsafe fn bury(&self, reclaim: Box<dyn FnOnce()>) {
+        let grave = Box::into_raw(Box::new(Grave {
+            next: ptr::null_mut(),
+            reclaim,
+        }));
+        // counted up front so that a concurrent reclaim never observes more
+        // graves than were counted.
+        self.inner.buried.fetch_add(1, Ordering::Relaxed);
+        let mut head = self.inner.graveyard.load(Ordering::Acquire);
+        loop {
+            if head == CLOSED {
+                // the thread is gone, nothing can destroy the value anymore.
+                self.inner.buried.fetch_sub(1, Ordering::Relaxed);
+                return;
+            }
+            (*grave).next = head;
+            match self.inner.graveyard.compare_exchange_weak(
+                head,
+                grave,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return,
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    /// Destroys all values that were buried by foreign threads.
+    ///
+    /// Does nothing unless called on the home thread.  Values bound to a task
+    /// can be accessed from other threads, so callers cannot rely on that.
+    #[inline(always)]
+    pub(crate) fn reclaim(&self) {
+        let head = self.inner.graveyard.load(Ordering::Acquire);
+        if !head.is_null() && head != CLOSED && self.is_current() {
+            self.reclaim_slow(ptr::null_mut());
+        }
+    }
+
+    /// Returns the number of buried values that were not reclaimed yet.
+    pub(crate) fn buried(&self) -> usize {
+        self.inner.buried.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of values buried for the current thread.
+    ///
+    /// Unlike `Home::current().buried()` this never creates a home, so it can
+    /// be used while the thread is shutting down.
+    pub(crate) fn buried_on_current() -> usize {
+        // nothing can be buried for a thread that never handed out its home.
+        if !HAS_HOME.try_with(Cell::get).unwrap_or(false) {
+            return 0;
+        }
+        HOME.try_with(|guard| guard.0.buried()).unwrap_or(0)
+    }
+
+    fn close_graveyard(&self) {
+        self.reclaim_slow(CLOSED);
+    }
+
+    #[inline(never)]
+    fn reclaim_slow(&self, replacement: *mut Grave) {
+        let head = self.inner.graveyard.swap(replacement, Ordering::AcqRel);
+        if head == CLOSED {
+            self.inner.graveyard.store(CLOSED, Ordering::Release);
+            return;
+        }
+
+        // restore the original drop order before running the destructors.
+        let mut graves = Vec::new();
+        let mut next = head;
+        while !next.is_null() {
+            // SAFETY: graves are only ever taken off the stack by swapping
+            // the head which gives us exclusive ownership of the whole list.
+            let grave = unsafe { Box::from_raw(next) };
+            next = grave.next;
+            graves.push(grave);
+        }
+        self.inner.buried.fetch_sub(graves.len(), Ordering::Relaxed);
+        for grave in graves.into_iter().rev() {
+            (grave.reclaim)();
+        }
+    }
+
+    fn lock_queue(&self) -> MutexGuard<'_, HomeQueue> {
+        // jobs never run while the lock is held, so poisoning is not a concern
+        match self.inner.queue.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+impl fmt::Debug for Home {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Home")
+            .field("thread_id", &self.inner.thread_id)
+            .field("thread_name", &self.inner.thread_name)
+            .finish()
+    }
+}
+
+/// Runs all closures that other threads queued for the current thread.
+///
+/// This is a shortcut for `Home::current().run_pending()`.  Returns the number
+/// of closures that were executed.
+pub fn pump() -> usize {
+    HOME.try_with(|guard| {
+        guard.0.reclaim();
+        guard.0.run_pending()
+    })
+    .unwrap_or(0)
+}
+
+/// Destroys all values that foreign threads buried for the current thread.
+pub(crate) fn reclaim() {
+    HOME.try_with(|guard| guard.0.reclaim()).ok();
+}
+
+/// Runs `f` against `wrapper` on `home` and hands the wrapper back.
+///
+/// If the current thread is the home thread `f` is invoked immediately.
+pub(crate) fn run_on<W, F, R>(home: &Home, wrapper: W, f: F) -> HomeTask<(W, R)>
+where
+    W: Send + 'static,
+    F: FnOnce(&mut W) -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let mut wrapper = wrapper;
+    if home.is_current() {
+        let rv = f(&mut wrapper);
+        HomeTask::ready(home.clone(), (wrapper, rv))
+    } else {
+        home.spawn(move || {
+            let rv = f(&mut wrapper);
+            (wrapper, rv)
+        })
+    }
+}
+
+enum TaskState<R> {
+    Pending(Option<Waker>),
+    Ready(R),
+    Abandoned,
+    Taken,
+}
+
+struct TaskSlot<R> {
+    state: Mutex<TaskState<R>>,
+    cond: Condvar,
+}
+
+impl<R> TaskSlot<R> {
+    fn lock(&self) -> MutexGuard<'_, TaskState<R>> {
+        match self.state.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn resolve(&self, state: TaskState<R>) {
+        let old = std::mem::replace(&mut *self.lock(), state);
+        self.cond.notify_all();
+        if let TaskState::Pending(Some(waker)) = old {
+            waker.wake();
+        }
+    }
+
+    fn take(&self, state: &mut TaskState<R>) -> Option<Result<R, TaskAbandoned>> {
+        match std::mem::replace(state, TaskState::Taken) {
+            TaskState::Ready(rv) => Some(Ok(rv)),
+            TaskState::Abandoned => Some(Err(TaskAbandoned)),
+            TaskState::Taken => panic!("home task result was already taken"),
+            pending => {
+                *state = pending;
+                None
+            }
+        }
+    }
+}
+
+/// Completes a [`HomeTask`].  If dropped before completion the task is
+/// abandoned.
+pub(crate) struct TaskCompleter<R> {
+    slot: Option<Arc<TaskSlot<R>>>,
+}
+
+impl<R> TaskCompleter<R> {
+    pub(crate) fn complete(mut self, value: R) {
+        if let Some(slot) = self.slot.take() {
+            slot.resolve(TaskState::Ready(value));
+        }
+    }
+}
+
+impl<R> Drop for TaskCompleter<R> {
+    fn drop(&mut self) {
+        if let Some(slot) = self.slot.take() {
+            slot.resolve(TaskState::Abandoned);
+        }
+    }
+}
+
+/// A `Send` handle to the result of a closure that runs on a [`Home`].
+///
+/// The result can either be awaited by blocking with [`wait`](Self::wait) or,
+/// with the `future` feature, by polling the task as a future.
+pub struct HomeTask<R> {
+    home: Home,
+    slot: Arc<TaskSlot<R>>,
+}
+
+impl<R> HomeTask<R> {
+    pub(crate) fn new(home: Home) -> (HomeTask<R>, TaskCompleter<R>) {
+        let slot = Arc::new(TaskSlot {
+            state: Mutex::new(TaskState::Pending(None)),
+            cond: Condvar::new(),
+        });
+        let completer = TaskCompleter {
+            slot: Some(slot.clone()),
+        };
+        (HomeTask { home, slot }, completer)
+    }
+
+    pub(crate) fn ready(home: Home, value: R) -> HomeTask<R> {
+        let (task, completer) = HomeTask::new(home);
+        completer.complete(value);
+        task
+    }
+
+    /// Returns the home the closure runs on.
+    pub fn home(&self) -> &Home {
+        &self.home
+    }
+
+    /// Returns `true` if the closure finished or was abandoned.
+    pub fn is_finished(&self) -> bool {
+        !matches!(*self.slot.lock(), TaskState::Pending(_))
+    }
+
+    /// Blocks until the closure ran and returns its result.
+    ///
+    /// When invoked on the home thread itself, pending work is pumped first
+    /// so that waiting on a task queued for the current thread works.
+    ///
+    /// Returns [`TaskAbandoned`] if the closure will never run, for instance
+    /// because the home thread shut down or the closure panicked.
+    pub fn wait(self) -> Result<R, TaskAbandoned> {
+        if self.home.is_current() {
+            self.home.run_pending();
+        }
+        let mut state = self.slot.lock();
+        loop {
+            if let Some(rv) = self.slot.take(&mut state) {
+                return rv;
+            }
+            state = match self.slot.cond.wait(state) {
+                Ok(guard) => guard,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+        }
+    }
+}
+
+impl<R> fmt::Debug for HomeTask<R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HomeTask")
+            .field("home", &self.home)
+            .field("finished", &self.is_finished())
+            .finish()
+    }
+}
+
+#[cfg(feature = "future")]
+impl<R> std::future::Future for HomeTask<R> {
+    type Output = Result<R, TaskAbandoned>;
+
+    fn poll(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        let mut state = self.slot.lock();
+        match self.slot.take(&mut state) {
+            Some(rv) => std::task::Poll::Ready(rv),
+            None => {
+                *state = TaskState::Pending(Some(cx.waker().clone()));
+                std::task::Poll::Pending
+            }
+        }
+    }
+}
+
+#[test]
+fn test_graveyard() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    let home = Home::current();
+    let counter = Rc::new(Cell::new(0));
+    for _ in 0..3 {
+        let counter = counter.clone();
+        unsafe { home.bury(Box::new(move || counter.set(counter.get() + 1))) };
+    }
+    assert_eq!(counter.get(), 0);
+    pump();
+    assert_eq!(counter.get(), 3);
+}
+
+#[test]
+fn test_graveyard_closed() {
+    let home = thread::spawn(Home::current).join().unwrap();
+    let value = std::sync::Arc::new(());
+    let captured = value.clone();
+    unsafe { home.bury(Box::new(move || drop(captured))) };
+    // the value must be leaked rather than dropped here
+    assert_eq!(std::sync::Arc::strong_count(&value), 2);
+}
+
+#[test]
+fn test_spawn_and_pump() {
+    use std::sync::mpsc::channel;
+
+    let home = Home::current();
+    let (tx, rx) = channel();
+    let t = thread::spawn(move || {
+        let task = home.spawn(|| thread::current().id());
+        tx.send(()).unwrap();
+        task.wait()
+    });
+    rx.recv().unwrap();
+    assert_eq!(pump(), 1);
+    assert_eq!(t.join().unwrap().unwrap(), thread::current().id());
+}
+
+#[test]
+fn test_wait_on_home_thread() {
+    let task = Home::current().spawn(|| 42);
+    assert!(!task.is_finished());
+    assert_eq!(task.wait().unwrap(), 42);
+}
+
+#[test]
+fn test_abandoned_on_thread_exit() {
+    let home = thread::spawn(Home::current).join().unwrap();
+    let task = home.spawn(|| 42);
+    assert!(task.is_finished());
+    assert!(task.wait().is_err());
+}
+
+#[test]
+#[should_panic]
+fn test_run_pending_other_thread() {
+    let home = Home::current();
+    thread::spawn(move || {
+        home.run_pending();
+    })
+    .join()
+    .unwrap();
+}
@@ -0,0 +1,387 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+
+use crate::errors::TaskAbandoned;
+use crate::home::{Home, HomeTask};
+use crate::policy::ViolationPolicy;
+use crate::stack_token;
+use crate::sticky::Sticky;
+
+/// A future that is always polled on the thread that created it.
+///
+/// Unlike a [`Fragile`](crate::Fragile) or [`Sticky`] wrapping a future, a
+/// [`HomeBound`] future can be polled from any thread.  When it is polled on
+/// a foreign thread, polling the inner future is handed off to the [`Home`] of
+/// the owning thread and the foreign poll returns [`Poll::Pending`].  Once the
+/// owning thread ran the poll with [`pump`](crate::pump), the waker of the
+/// foreign task is woken so that it can pick up the result.  This makes it
+/// possible to await a non `Send` future from a `Send` context as long as the
+/// owning thread keeps pumping its home.
+///
+/// The output of the future is moved to the thread that awaits it and thus
+/// needs to be `Send`.  Should the owning thread shut down before the future
+/// completes, the [`HomeBound`] resolves to [`TaskAbandoned`].
+///
+/// ```
+/// use std::rc::Rc;
+/// use std::sync::mpsc::channel;
+/// use std::thread;
+/// use fragile::HomeBound;
+///
+/// let future = HomeBound::new(async {
+///     let value = Rc::new(21);
+///     *value * 2
+/// });
+///
+/// let (tx, rx) = channel();
+/// thread::spawn(move || {
+///     tx.send(futures_executor::block_on(future)).unwrap();
+/// });
+///
+/// // the future makes progress while the owning thread pumps its home
+/// let rv = loop {
+///     fragile::pump();
+///     if let Ok(rv) = rx.try_recv() {
+///         break rv;
+///     }
+///     thread::yield_now();
+/// };
+/// assert_eq!(rv.unwrap(), 42);
+/// ```
+pub struct HomeBound<F: Future + 'static> {
+    bound: Arc<Sticky<Bound<F>>>,
+    waker: Arc<ForwardWaker>,
+    home: Home,
+    task: Option<HomeTask<Option<F::Output>>>,
+}
+
+/// The part of a [`HomeBound`] that lives on the owning thread.
+struct Bound<F> {
+    // `None` once the future completed.
+    future: RefCell<Option<Pin<Box<F>>>>,
+    waker: Arc<ForwardWaker>,
+}
+
+impl<F> Drop for Bound<F> {
+    fn drop(&mut self) {
+        // if the owning thread shuts down the future is dropped without ever
+        // completing.  wake the foreign task so that it notices.
+        self.waker.wake_by_ref();
+    }
+}
+
+/// Forwards wakes of the inner future to the task that last polled the
+/// [`HomeBound`].
+struct ForwardWaker {
+    waker: Mutex<Option<Waker>>,
+    // set when the inner future needs to be polled again.
+    woken: AtomicBool,
+}
+
+impl ForwardWaker {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Waker>> {
+        match self.waker.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+impl Wake for ForwardWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.woken.store(true, Ordering::SeqCst);
+        // wake outside of the lock as the waker might poll right away.
+        let waker = self.lock().clone();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl<F: Future + 'static> HomeBound<F> {
+    /// Binds `future` to the current thread.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the thread local registry of the current thread was already
+    /// destroyed.  This can only happen if this is called from the destructor
+    /// of a thread local.
+    #[track_caller]
+    pub fn new(future: F) -> Self {
+        let waker = Arc::new(ForwardWaker {
+            waker: Mutex::new(None),
+            // the first foreign poll always needs to hand off.
+            woken: AtomicBool::new(true),
+        });
+        let mut bound = Sticky::new(Bound {
+            future: RefCell::new(Some(Box::pin(future))),
+            waker: waker.clone(),
+        });
+        // the future is routinely dropped on foreign threads and reclaimed by
+        // its home, which is not a violation.
+        bound.set_violation_policy(ViolationPolicy::Leak);
+        HomeBound {
+            home: bound.home().clone(),
+            bound: Arc::new(bound),
+            waker,
+            task: None,
+        }
+    }
+
+    /// Returns the [`Home`] of the thread that polls the future.
+    pub fn home(&self) -> &Home {
+        &self.home
+    }
+}
+
+/// Polls the inner future.  Must only be called on the owning thread.
+fn poll_bound<F: Future>(bound: &Sticky<Bound<F>>, cx: &mut Context<'_>) -> Option<F::Output> {
+    stack_token!(tok);
+    // the borrow fails if the future pumps its own home while being polled,
+    // in which case the outer poll is still in charge.
+    let mut future = bound.get(tok).future.try_borrow_mut().ok()?;
+    match future.as_mut()?.as_mut().poll(cx) {
+        Poll::Ready(rv) => {
+            *future = None;
+            Some(rv)
+        }
+        Poll::Pending => None,
+    }
+}
+
+impl<F> Future for HomeBound<F>
+where
+    F: Future + 'static,
+    F::Output: Send + 'static,
+{
+    type Output = Result<F::Output, TaskAbandoned>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let is_home = this.home.is_current();
+
+        loop {
+            if let Some(task) = this.task.as_mut() {
+                match Pin::new(task).poll(cx) {
+                    Poll::Ready(Ok(Some(rv))) => {
+                        this.task = None;
+                        return Poll::Ready(Ok(rv));
+                    }
+                    Poll::Ready(Ok(None)) => this.task = None,
+                    Poll::Ready(Err(err)) => {
+                        this.task = None;
+                        return Poll::Ready(Err(err));
+                    }
+                    // on the owning thread there is no need to wait for the
+                    // queued poll.
+                    Poll::Pending if !is_home => return Poll::Pending,
+                    Poll::Pending => {}
+                }
+            }
+
+            // the inner future only ever sees the forwarding waker so that
+            // its wakes reach whoever polled last, at home or elsewhere.
+            *this.waker.lock() = Some(cx.waker().clone());
+            if is_home {
+                this.waker.woken.store(false, Ordering::SeqCst);
+                let waker = Waker::from(this.waker.clone());
+                return match poll_bound(&this.bound, &mut Context::from_waker(&waker)) {
+                    Some(rv) => Poll::Ready(Ok(rv)),
+                    None => Poll::Pending,
+                };
+            }
+
+            if !this.waker.woken.swap(false, Ordering::SeqCst) {
+                return Poll::Pending;
+            }
+            let bound = this.bound.clone();
+            let waker = this.waker.clone();
+            this.task = Some(this.home.spawn(move || {
+                let waker = Waker::from(waker);
+                poll_bound(&bound, &mut Context::from_waker(&waker))
+            }));
+        }
+    }
+}
+
+impl<F: Future + 'static> Drop for HomeBound<F> {
+    fn drop(&mut self) {
+        // nobody is interested in wakes any more.
+        self.waker.lock().take();
+    }
+}
+
+impl<F: Future + 'static> fmt::Debug for HomeBound<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HomeBound")
+            .field("home", &self.home)
+            .finish()
+    }
+}
+
+#[test]
+fn test_poll_on_home() {
+    use std::rc::Rc;
+
+    let value = Rc::new(21);
+    let future = HomeBound::new(async move { *value * 2 });
+    assert_eq!(futures_executor::block_on(future).unwrap(), 42);
+}
+
+#[test]
+fn test_poll_elsewhere() {
+    use std::rc::Rc;
+    use std::sync::mpsc::channel;
+    use std::thread;
+
+    // yields once after waking itself.
+    struct YieldNow(bool);
+    impl Future for YieldNow {
+        type Output = ();
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                Poll::Ready(())
+            } else {
+                self.0 = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+
+    let home = thread::current().id();
+    let future = HomeBound::new(async move {
+        let value = Rc::new(thread::current().id());
+        YieldNow(false).await;
+        *value
+    });
+
+    let (tx, rx) = channel();
+    let t = thread::spawn(move || {
+        let rv = futures_executor::block_on(future);
+        tx.send(()).unwrap();
+        rv
+    });
+
+    // every poll of the inner future is handed off to this thread
+    let mut polls = 0;
+    while rx.try_recv().is_err() {
+        polls += crate::pump();
+        thread::yield_now();
+    }
+    assert_eq!(polls, 2);
+    assert_eq!(t.join().unwrap().unwrap(), home);
+}
+
+#[test]
+fn test_poll_at_home_then_elsewhere() {
+    use std::sync::mpsc::{channel, Sender};
+    use std::thread;
+    use std::time::Duration;
+
+    struct Noop;
+    impl Wake for Noop {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    struct Notify(Mutex<Sender<()>>);
+    impl Wake for Notify {
+        fn wake(self: Arc<Self>) {
+            self.0.lock().unwrap().send(()).ok();
+        }
+    }
+
+    // completes once `ready` is set and remembers the waker it saw last.
+    struct Signal {
+        ready: Arc<AtomicBool>,
+        waker: Arc<Mutex<Option<Waker>>>,
+    }
+    impl Future for Signal {
+        type Output = u32;
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<u32> {
+            if self.ready.load(Ordering::SeqCst) {
+                Poll::Ready(42)
+            } else {
+                *self.waker.lock().unwrap() = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    let ready = Arc::new(AtomicBool::new(false));
+    let signal_waker = Arc::new(Mutex::new(None));
+    let mut future = HomeBound::new(Signal {
+        ready: ready.clone(),
+        waker: signal_waker.clone(),
+    });
+    let noop = Waker::from(Arc::new(Noop));
+    assert!(Pin::new(&mut future)
+        .poll(&mut Context::from_waker(&noop))
+        .is_pending());
+
+    let (polled_tx, polled_rx) = channel();
+    let done = Arc::new(AtomicBool::new(false));
+    let t = thread::spawn({
+        // also set if the thread panics.
+        struct Done(Arc<AtomicBool>);
+        impl Drop for Done {
+            fn drop(&mut self) {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+        let done = Done(done.clone());
+        move || {
+            let _done = done;
+            let (wake_tx, wake_rx) = channel();
+            let waker = Waker::from(Arc::new(Notify(Mutex::new(wake_tx))));
+            let mut cx = Context::from_waker(&waker);
+            assert!(Pin::new(&mut future).poll(&mut cx).is_pending());
+            polled_tx.send(()).unwrap();
+            loop {
+                wake_rx
+                    .recv_timeout(Duration::from_secs(10))
+                    .expect("foreign task was never woken");
+                if let Poll::Ready(rv) = Pin::new(&mut future).poll(&mut cx) {
+                    break rv;
+                }
+            }
+        }
+    });
+    polled_rx.recv().unwrap();
+
+    // the waker captured by the poll at home must reach the foreign task.
+    ready.store(true, Ordering::SeqCst);
+    let waker = signal_waker.lock().unwrap().take().unwrap();
+    waker.wake();
+    while !done.load(Ordering::SeqCst) {
+        crate::pump();
+        thread::yield_now();
+    }
+    assert_eq!(t.join().unwrap().unwrap(), 42);
+}
+
+#[test]
+fn test_owner_exited() {
+    use std::rc::Rc;
+    use std::thread;
+
+    let future = thread::spawn(|| {
+        HomeBound::new(async {
+            let value = Rc::new(42);
+            std::future::pending::<()>().await;
+            *value
+        })
+    })
+    .join()
+    .unwrap();
+    assert!(futures_executor::block_on(future).is_err());
+}
@@ -17,15 +17,17 @@
 //!
 //! A [`Sticky`] on the other hand does not actually send the `T` around but keeps
 //! it stored in the original thread's thread local storage.  If it gets dropped
-//! in the originating thread it gets cleaned up immediately, otherwise it leaks
-//! until the thread shuts down naturally.  [`Sticky`] because it borrows into the
+//! in the originating thread it gets cleaned up immediately, otherwise it is
+//! handed back to the originating thread which cleans it up the next time it
+//! interacts with this crate or shuts down naturally.  [`Sticky`] because it borrows into the
 //! TLS also requires you to "prove" that you are not doing any funny business with
 //! the borrowed value that lives for longer than the current stack frame which
 //! results in a slightly more complex API.
 //!
 //! There is a third typed called [`SemiSticky`] which shares the API with [`Sticky`]
-//! but internally uses a boxed [`Fragile`] if the type does not actually need a dtor
-//! in which case [`Fragile`] is preferred.
+//! but internally uses a [`Fragile`] if the type does not actually need a dtor
+//! in which case [`Fragile`] is preferred.  The choice can also be made explicitly
+//! with a [`Strategy`], or fixed in the type with a [`Storage`].
 //!
 //! # Fragile Usage
 //!
@@ -73,6 +75,78 @@
 //!     .unwrap();
 //! ```
 //!
+//! # Shared Handles
+//!
+//! Cloning a [`Sticky`] clones the wrapped value.  To hand out multiple references to
+//! the same value instead, a [`Sticky`] can be turned into a [`StickyRc`] with
+//! [`Sticky::share`].  The value is destroyed once the last handle is gone.
+//! [`Sticky::downgrade`] creates a [`StickyWeak`] handle which does not keep the
+//! value alive and can be upgraded on the owning thread as long as the value exists.
+//! A [`StickyKey`] is a copyable key to a value in the registry of a thread which can
+//! be embedded in messages and redeemed on the owning thread.
+//!
+//! A [`PerThread`] on the other hand gives every thread that accesses it a value of
+//! its own.  Unlike [`thread_local!`](std::thread_local) it can be stored in a struct.
+//!
+//! # Home Threads
+//!
+//! Every wrapper remembers the [`Home`] of the thread that created it.  Rather
+//! than failing on a foreign thread, the value can be marshalled back to its
+//! home with `run_on_home` which queues a closure for the owning thread and
+//! returns a `Send` [`HomeTask`] for the result.  The owning thread drains its
+//! queue by calling [`pump`] (or [`Home::run_pending`]) at a convenient point,
+//! for instance once per iteration of its event loop.
+//!
+//! ```
+//! use std::rc::Rc;
+//! use std::thread;
+//! use fragile::Fragile;
+//!
+//! let val = Fragile::new(Rc::new(21));
+//! let worker = thread::spawn(move || {
+//!     val.run_on_home(|value| **value * 2)
+//! });
+//! let task = worker.join().unwrap();
+//!
+//! // the closure runs once the owning thread pumps its home
+//! fragile::pump();
+//! let (_val, rv) = task.wait().unwrap();
+//! assert_eq!(rv, 42);
+//! ```
+//!
+//! With the `future` feature, a non `Send` future can be wrapped in a [`HomeBound`]
+//! which can be awaited from any thread.  Polls on foreign threads are handed off to
+//! the home of the owning thread which polls the inner future the next time it pumps.
+//! The [`executor`] module provides a minimal single threaded executor which pumps its
+//! home while it runs and accepts work from other threads.  Similarly, a
+//! [`FragileWaker`] turns the thread bound wake callback of a local event loop into a
+//! [`Waker`](std::task::Waker) that can be woken from any thread.
+//!
+//! # Task Affinity
+//!
+//! A [`Fragile`] is bound to the thread that created it by default.  Futures that run
+//! on work-stealing executors however can move between threads from one poll to the
+//! next.  For such cases a [`Fragile`] can be bound to a task instead by using
+//! [`TaskAffinity`] which tracks the current task for futures wrapped with
+//! [`TaskAffinity::bind`].  Custom contexts can be supported by implementing the
+//! [`Affinity`] trait.  [`Sticky`] and [`SemiSticky`] always stay bound to a thread.
+//!
+//! # Handing Off Values
+//!
+//! Values that are not `Send` but can be safely moved to another thread as a whole
+//! can implement the unsafe [`ThreadMovable`] trait which is implemented for all `Send`
+//! types already.  The owning thread can then give up a [`Fragile`] or [`Sticky`] with
+//! `handoff` and exactly one receiving thread can [`claim`](Handoff::claim) it which
+//! binds the wrapper to that thread.  This is useful for thread pools that pass work
+//! objects from one stage to the next.
+//!
+//! # Remote Values
+//!
+//! Values that should permanently live on a thread of their own can be placed there
+//! with [`Remote::spawn`].  The returned [`Remote`] handles can be cloned and used
+//! from any thread to run closures against the value on its thread.  The thread
+//! keeps pumping its home until the last handle is dropped.
+//!
 //! # Why?
 //!
 //! Most of the time trying to use this crate is going to indicate some code smell.  But
@@ -84,12 +158,33 @@
 //! # Drop / Cleanup Behavior
 //!
 //! All types will try to eagerly drop a value if they are dropped on the right thread.
-//! [`Sticky`] and [`SemiSticky`] will however temporarily leak memory until a thread
-//! shuts down if the value is dropped on the wrong thread.  The benefit however is that
-//! if you have that type of situation, and you can live with the consequences, the
-//! type is not panicking.  A [`Fragile`] dropped in the wrong thread will not just panic,
-//! it will effectively also tear down the process because panicking in destructors is
-//! non recoverable.
+//! [`Sticky`] and [`SemiSticky`] will however temporarily leak memory if the value is
+//! dropped on the wrong thread.  The value is queued up in a graveyard of the owning
+//! thread and destroyed the next time that thread creates or accesses a wrapper, calls
+//! [`pump`] or shuts down.  The benefit however is that if you have that type of
+//! situation, and you can live with the consequences, the type is not panicking.  A
+//! [`Fragile`] dropped in the wrong thread will not just panic, it will effectively also
+//! tear down the process because panicking in destructors is non recoverable.  To avoid
+//! this, a [`Fragile`] can be created with [`Fragile::new_deferred`] in which case it
+//! uses the same graveyard as [`Sticky`] instead of panicking.
+//!
+//! Once the owning thread exited, a wrapped value can never be reached again.  The
+//! `owner_alive` and `is_orphaned` methods report this, and dropping an orphaned
+//! wrapper on any thread silently leaks whatever is left of the value.
+//!
+//! Long running threads can use [`scope`] to destroy all values that [`Sticky`] wrappers
+//! created within a scope once it ends, no matter where their handles ended up.
+//!
+//! The [`registry`] module can be used to inspect which values of [`Sticky`] wrappers
+//! a thread is currently holding on to and to report values left behind when a
+//! thread shuts down.
+//!
+//! # Violation Policies
+//!
+//! What happens when a wrapper is used from the wrong thread can be configured
+//! with a [`ViolationPolicy`], either globally with [`set_violation_policy`] or
+//! per value with the `set_violation_policy` method of the wrappers.  This for
+//! instance allows leaking and logging in production while panicking in tests.
 //!
 //! # Features
 //!
@@ -101,27 +196,75 @@
 //! [`Future`](std::future::Future) crate from the standard library to
 //! automatically wrap futures.  The `stream` crate does the same for the
 //! `future_core::Stream` type.
+//! It also forwards `FusedFuture` and `FusedStream`.  The `sink` feature
+//! implements `futures_sink::Sink` and the `io` feature implements
+//! `AsyncRead`, `AsyncWrite` and `AsyncSeek` from `futures_io`.  `AsyncBufRead`
+//! is only implemented for [`Fragile`] as the buffer borrowed from a [`Sticky`]
+//! could outlive the storage of its thread.  [`Sticky`] and [`SemiSticky`]
+//! offer `poll_fill_buf_with` instead which ties the buffer to a [`StackToken`].
+mod affinity;
 mod errors;
+#[cfg(feature = "future")]
+pub mod executor;
 mod fragile;
-mod registry;
+mod handoff;
+mod home;
+#[cfg(feature = "future")]
+mod homebound;
+mod perthread;
+mod policy;
+pub mod registry;
+mod remote;
+mod scope;
 mod semisticky;
 mod sticky;
+mod stickykey;
+mod stickyrc;
+mod stickyweak;
+mod thread_id;
+mod waker;
 
 #[cfg(feature = "future")]
 mod futures;
 
 use std::marker::PhantomData;
 
-pub use crate::errors::InvalidThreadAccess;
+#[cfg(feature = "future")]
+pub use crate::affinity::TaskBound;
+pub use crate::affinity::{Affinity, TaskAffinity, ThreadAffinity};
+pub use crate::errors::{
+    AccessErrorReason, IntoInnerError, InvalidThreadAccess, TaskAbandoned, WrapperKind,
+};
 pub use crate::fragile::Fragile;
-pub use crate::semisticky::SemiSticky;
+pub use crate::handoff::{Handoff, ThreadMovable};
+pub use crate::home::{pump, Home, HomeTask};
+#[cfg(feature = "future")]
+pub use crate::homebound::HomeBound;
+pub use crate::perthread::PerThread;
+pub use crate::policy::{
+    set_violation_policy, violation_policy, ViolationInfo, ViolationKind, ViolationPolicy,
+};
+pub use crate::remote::Remote;
+pub use crate::scope::{scope, Scope};
+pub use crate::semisticky::{
+    DeferredStorage, DynamicStorage, FragileStorage, SemiSticky, StickyStorage, Storage, Strategy,
+};
 pub use crate::sticky::Sticky;
+pub use crate::stickykey::StickyKey;
+pub use crate::stickyrc::StickyRc;
+pub use crate::stickyweak::{StickyRef, StickyWeak};
+pub use crate::waker::FragileWaker;
 
 /// A token that is placed to the stack to constrain lifetimes.
 ///
 /// For more information about how these work see the documentation of
 /// [`stack_token!`] which is the only way to create this token.
-pub struct StackToken(PhantomData<*const ()>);
+pub struct StackToken {
+    // the scope depth at the time the token was created.  values of scopes
+    // entered later cannot be borrowed with the token.
+    depth: usize,
+    _marker: PhantomData<*const ()>,
+}
 
 impl StackToken {
     /// Stack tokens must only be created on the stack.
@@ -129,7 +272,24 @@ impl StackToken {
     pub unsafe fn __private_new() -> StackToken {
         // we place a const pointer in there to get a type
         // that is neither Send nor Sync.
-        StackToken(PhantomData)
+        StackToken {
+            depth: registry::acquire_token(),
+            _marker: PhantomData,
+        }
+    }
+
+    pub(crate) fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+impl Drop for StackToken {
+    fn drop(&mut self) {
+        // values of a scope that ended while this token was alive are kept
+        // until the token is gone.
+        if self.depth > 0 {
+            registry::release_token(self.depth);
+        }
     }
 }
 
@@ -0,0 +1,299 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::panic::Location;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+use crate::errors::{AccessErrorReason, InvalidThreadAccess, WrapperKind};
+use crate::home::Home;
+use crate::registry::{self, ItemId};
+use crate::StackToken;
+
+// maps the ids of the per thread values to their entries in the registry of
+// the current thread.
+thread_local!(static SLOTS: RefCell<HashMap<usize, ItemId>> = RefCell::new(HashMap::new()));
+
+/// The part of a [`PerThread`] that is allocated on first use.
+struct State {
+    id: usize,
+    // the threads that created a value and have not exited yet.
+    owners: Mutex<Vec<(Home, ItemId)>>,
+}
+
+impl State {
+    fn owners(&self) -> MutexGuard<'_, Vec<(Home, ItemId)>> {
+        // no foreign code runs while the lock is held
+        match self.owners.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+/// A value that exists once for every thread that accesses it.
+///
+/// Unlike [`thread_local!`](std::thread_local) a [`PerThread`] is an ordinary
+/// value that can be stored in a struct as well as in a `static`.  Every
+/// thread that accesses it gets its own value which is created with the init
+/// function on first access.  The values are stored in the same thread local
+/// registry as the values of [`Sticky`](crate::Sticky) wrappers and are
+/// destroyed when their thread exits or once the [`PerThread`] is dropped,
+/// whichever happens first.  Values of other threads are destroyed by their
+/// thread the next time it interacts with this crate.
+///
+/// Like [`Sticky`](crate::Sticky), borrowing the value requires a
+/// [`StackToken`].
+///
+/// ```
+/// use std::cell::Cell;
+/// use std::thread;
+/// use fragile::PerThread;
+///
+/// static COUNTER: PerThread<Cell<u32>> = PerThread::new(|| Cell::new(0));
+///
+/// COUNTER.with(|x| x.set(x.get() + 1));
+/// thread::spawn(|| assert_eq!(COUNTER.with(|x| x.get()), 0))
+///     .join()
+///     .unwrap();
+/// assert_eq!(COUNTER.with(|x| x.get()), 1);
+/// ```
+pub struct PerThread<T: 'static, F = fn() -> T> {
+    init: F,
+    // allocated on first access so that `new` can be const.
+    state: AtomicPtr<State>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: 'static, F> PerThread<T, F> {
+    /// Creates a new [`PerThread`] that creates the value of every thread
+    /// with `init`.
+    pub const fn new(init: F) -> PerThread<T, F> {
+        PerThread {
+            init,
+            state: AtomicPtr::new(ptr::null_mut()),
+            _marker: PhantomData,
+        }
+    }
+
+    fn state(&self) -> &State {
+        static COUNTER: AtomicUsize = AtomicUsize::new(1);
+
+        let mut state = self.state.load(Ordering::Acquire);
+        if state.is_null() {
+            let new = Box::into_raw(Box::new(State {
+                id: COUNTER.fetch_add(1, Ordering::Relaxed),
+                owners: Mutex::new(Vec::new()),
+            }));
+            state = match self.state.compare_exchange(
+                ptr::null_mut(),
+                new,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => new,
+                Err(existing) => {
+                    // SAFETY: the state lost the race and was never shared.
+                    drop(unsafe { Box::from_raw(new) });
+                    existing
+                }
+            };
+        }
+        // SAFETY: the state is only freed when the `PerThread` is dropped.
+        unsafe { &*state }
+    }
+}
+
+impl<T: 'static, F: Fn() -> T> PerThread<T, F> {
+    #[track_caller]
+    fn lookup(&self) -> Result<*mut T, InvalidThreadAccess> {
+        let state = self.state();
+        let home = Home::current();
+        home.reclaim();
+
+        let slot = SLOTS
+            .try_with(|slots| slots.borrow().get(&state.id).copied())
+            .map_err(|_| destroyed::<T>(&home))?;
+        if let Some(item_id) = slot {
+            // the entry is missing if the registry destroyed it while the
+            // thread shuts down.  per thread values do not belong to a scope,
+            // so they can be borrowed with any token.
+            if let Ok(ptr) = registry::get(item_id, usize::MAX) {
+                return Ok(ptr.cast());
+            }
+        }
+
+        let value = (self.init)();
+        // the init function might have accessed the value already.
+        let item_id = SLOTS
+            .try_with(|slots| slots.borrow().get(&state.id).copied())
+            .map_err(|_| destroyed::<T>(&home))?;
+        if let Some(ptr) = item_id.and_then(|item_id| registry::get(item_id, usize::MAX).ok()) {
+            drop(value);
+            return Ok(ptr.cast());
+        }
+
+        let entry = registry::Entry::new(value, Location::caller());
+        let item_id = match registry::insert_unscoped(entry) {
+            Ok(item_id) => item_id,
+            Err(entry) => {
+                // SAFETY: the entry never made it into the registry.
+                unsafe { (entry.drop)(entry.ptr) };
+                return Err(destroyed::<T>(&home));
+            }
+        };
+        let ptr = registry::get(item_id, usize::MAX).map_err(|_| destroyed::<T>(&home))?;
+        SLOTS
+            .try_with(|slots| slots.borrow_mut().insert(state.id, item_id))
+            .ok();
+        let mut owners = state.owners();
+        owners.retain(|(home, _)| home.is_alive());
+        owners.push((home, item_id));
+        Ok(ptr.cast())
+    }
+
+    /// Borrows the value of the current thread.
+    ///
+    /// The value is created with the init function if the current thread did
+    /// not access it before.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the thread local registry of the current thread was already
+    /// destroyed.  For a non-panicking variant, use [`try_get`](Self::try_get).
+    #[track_caller]
+    pub fn get<'stack>(&'stack self, _proof: &'stack StackToken) -> &'stack T {
+        match self.lookup() {
+            // SAFETY: the value is only destroyed when the thread exits or
+            // the `PerThread` is dropped.
+            Ok(ptr) => unsafe { &*ptr },
+            Err(_) => panic!(
+                "trying to access per-thread value after the thread local registry was destroyed."
+            ),
+        }
+    }
+
+    /// Tries to borrow the value of the current thread.
+    ///
+    /// Returns an error with the [`RegistryDestroyed`](AccessErrorReason::RegistryDestroyed)
+    /// reason if the thread local registry of the current thread was already
+    /// destroyed.
+    #[track_caller]
+    pub fn try_get<'stack>(
+        &'stack self,
+        _proof: &'stack StackToken,
+    ) -> Result<&'stack T, InvalidThreadAccess> {
+        // SAFETY: see `get`.
+        self.lookup().map(|ptr| unsafe { &*ptr })
+    }
+
+    /// Invokes `f` with the value of the current thread.
+    ///
+    /// # Panics
+    ///
+    /// Panics like [`get`](Self::get).
+    #[track_caller]
+    pub fn with<R, G: FnOnce(&T) -> R>(&self, f: G) -> R {
+        crate::stack_token!(tok);
+        f(self.get(tok))
+    }
+}
+
+fn destroyed<T>(home: &Home) -> InvalidThreadAccess {
+    InvalidThreadAccess::with_reason::<T>(
+        AccessErrorReason::RegistryDestroyed,
+        WrapperKind::PerThread,
+        home,
+    )
+}
+
+impl<T: 'static, F> Drop for PerThread<T, F> {
+    fn drop(&mut self) {
+        let state = *self.state.get_mut();
+        if state.is_null() {
+            return;
+        }
+        // SAFETY: the state is no longer reachable through `self`.
+        let state = unsafe { Box::from_raw(state) };
+        let id = state.id;
+        for (home, item_id) in state.owners().drain(..) {
+            let release = move || {
+                SLOTS.try_with(|slots| slots.borrow_mut().remove(&id)).ok();
+                if let Some(entry) = registry::release(item_id) {
+                    // SAFETY: the entry was removed from the registry.
+                    unsafe { (entry.drop)(entry.ptr) };
+                }
+            };
+            if home.is_current() {
+                release();
+            } else {
+                // SAFETY: the registry is only touched on the home thread.
+                unsafe { home.bury(Box::new(release)) };
+            }
+        }
+    }
+}
+
+impl<T: 'static, F> fmt::Debug for PerThread<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PerThread").finish()
+    }
+}
+
+#[test]
+fn test_per_thread() {
+    use std::cell::Cell;
+    use std::sync::Arc;
+    use std::thread;
+
+    let val = Arc::new(PerThread::new(|| Cell::new(0)));
+    val.with(|x| x.set(x.get() + 1));
+    val.with(|x| x.set(x.get() + 1));
+    let other = val.clone();
+    let rv = thread::spawn(move || {
+        other.with(|x| x.set(x.get() + 40));
+        other.with(|x| x.get())
+    })
+    .join()
+    .unwrap();
+    assert_eq!(rv, 40);
+    crate::stack_token!(tok);
+    assert_eq!(val.get(tok).get(), 2);
+}
+
+#[test]
+fn test_drop() {
+    use std::rc::Rc;
+    use std::sync::mpsc::channel;
+    use std::sync::Arc;
+    use std::thread;
+
+    thread_local!(static VALUE: Rc<()> = Rc::new(()));
+
+    let before = registry::stats().entries();
+    let val = Arc::new(PerThread::new(|| VALUE.with(|x| x.clone())));
+    val.with(|_| ());
+    assert_eq!(registry::stats().entries(), before + 1);
+    assert_eq!(VALUE.with(Rc::strong_count), 2);
+
+    let (tx, rx) = channel();
+    let (done_tx, done_rx) = channel();
+    let other = val.clone();
+    let t = thread::spawn(move || {
+        other.with(|_| ());
+        drop(other);
+        tx.send(VALUE.with(Rc::strong_count)).unwrap();
+        done_rx.recv().unwrap();
+        // the value of this thread is destroyed by this thread.
+        crate::pump();
+        VALUE.with(Rc::strong_count)
+    });
+    assert_eq!(rx.recv().unwrap(), 2);
+    drop(val);
+    assert_eq!(registry::stats().entries(), before);
+    assert_eq!(VALUE.with(Rc::strong_count), 1);
+    done_tx.send(()).unwrap();
+    assert_eq!(t.join().unwrap(), 1);
+}
@@ -0,0 +1,207 @@
+use std::fmt;
+use std::mem;
+use std::panic::Location;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread::ThreadId;
+
+use crate::errors::WrapperKind;
+use crate::thread_id;
+
+/// Controls what happens when a wrapper is used from the wrong thread.
+///
+/// A policy can be set globally with [`set_violation_policy`] or for a single
+/// value with the `set_violation_policy` method of the wrappers.  The policy
+/// of a value takes precedence over the global one.  If neither is set, the
+/// historic behavior applies: accessing or polling from the wrong thread
+/// panics, dropping a [`Fragile`](crate::Fragile) on the wrong thread panics
+/// and dropping a [`Sticky`](crate::Sticky) on the wrong thread is silently
+/// accepted.
+///
+/// Because a reference to the value cannot be produced on the wrong thread,
+/// accessing a value always panics after the policy was consulted unless the
+/// policy aborts the process.  Polling a wrapped future or stream on the wrong
+/// thread returns `Poll::Pending` if the policy does not panic or abort.
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub enum ViolationPolicy {
+    /// Panics.
+    Panic,
+    /// Silently leaks the value.
+    ///
+    /// A [`Sticky`](crate::Sticky) is still reclaimed by the owning thread.
+    Leak,
+    /// Prints the violation to stderr and aborts the process.
+    Abort,
+    /// Prints the violation to stderr and otherwise behaves like `Leak`.
+    Log,
+    /// Invokes the callback and otherwise behaves like `Leak`.
+    Callback(fn(&ViolationInfo)),
+}
+
+/// The kind of operation that was attempted from the wrong thread.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ViolationKind {
+    /// The value was borrowed or taken.
+    Access,
+    /// The wrapper was dropped.
+    Drop,
+    /// The wrapped future or stream was polled.
+    Poll,
+}
+
+/// Information about a wrapper that was used from the wrong thread.
+#[derive(Clone, Debug)]
+pub struct ViolationInfo {
+    kind: ViolationKind,
+    wrapper: WrapperKind,
+    type_name: &'static str,
+    owner: ThreadId,
+    current: Option<ThreadId>,
+    location: &'static Location<'static>,
+}
+
+impl ViolationInfo {
+    #[track_caller]
+    pub(crate) fn new(
+        kind: ViolationKind,
+        wrapper: WrapperKind,
+        type_name: &'static str,
+        owner: ThreadId,
+    ) -> ViolationInfo {
+        ViolationInfo {
+            kind,
+            wrapper,
+            type_name,
+            owner,
+            current: thread_id::try_current(),
+            location: Location::caller(),
+        }
+    }
+
+    /// The operation that was attempted.
+    pub fn kind(&self) -> ViolationKind {
+        self.kind
+    }
+
+    /// The kind of wrapper holding the value.
+    pub fn wrapper(&self) -> WrapperKind {
+        self.wrapper
+    }
+
+    /// The name of the wrapped type.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// The thread that owns the value.
+    pub fn owner(&self) -> ThreadId {
+        self.owner
+    }
+
+    /// The thread that attempted the operation.
+    ///
+    /// This is `None` if the violation happened while the thread was shutting
+    /// down and its ID could no longer be determined.
+    pub fn current(&self) -> Option<ThreadId> {
+        self.current
+    }
+
+    /// The location of the caller.
+    pub fn location(&self) -> &'static Location<'static> {
+        self.location
+    }
+}
+
+impl fmt::Display for ViolationInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            ViolationKind::Access => write!(
+                f,
+                "trying to access wrapped value in {} container from incorrect thread.",
+                self.wrapper
+            ),
+            ViolationKind::Drop => write!(
+                f,
+                "destructor of {} object ran on wrong thread",
+                self.wrapper
+            ),
+            ViolationKind::Poll => write!(
+                f,
+                "trying to poll wrapped value in {} container from incorrect thread.",
+                self.wrapper
+            ),
+        }
+    }
+}
+
+const UNSET: usize = 0;
+const PANIC: usize = 1;
+const LEAK: usize = 2;
+const ABORT: usize = 3;
+const LOG: usize = 4;
+const CALLBACK: usize = 5;
+
+static GLOBAL_POLICY: AtomicUsize = AtomicUsize::new(UNSET);
+static GLOBAL_CALLBACK: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets the global [`ViolationPolicy`].
+///
+/// Passing `None` restores the default behavior.
+pub fn set_violation_policy(policy: Option<ViolationPolicy>) {
+    let tag = match policy {
+        None => UNSET,
+        Some(ViolationPolicy::Panic) => PANIC,
+        Some(ViolationPolicy::Leak) => LEAK,
+        Some(ViolationPolicy::Abort) => ABORT,
+        Some(ViolationPolicy::Log) => LOG,
+        Some(ViolationPolicy::Callback(f)) => {
+            // the callback is stored first so that a reader observing the tag
+            // always finds a valid function pointer.
+            GLOBAL_CALLBACK.store(f as usize, Ordering::Release);
+            CALLBACK
+        }
+    };
+    GLOBAL_POLICY.store(tag, Ordering::Release);
+}
+
+/// Returns the global [`ViolationPolicy`] if one was set.
+pub fn violation_policy() -> Option<ViolationPolicy> {
+    match GLOBAL_POLICY.load(Ordering::Acquire) {
+        PANIC => Some(ViolationPolicy::Panic),
+        LEAK => Some(ViolationPolicy::Leak),
+        ABORT => Some(ViolationPolicy::Abort),
+        LOG => Some(ViolationPolicy::Log),
+        CALLBACK => {
+            let f = GLOBAL_CALLBACK.load(Ordering::Acquire);
+            // SAFETY: only function pointers of this type are ever stored.
+            Some(ViolationPolicy::Callback(unsafe {
+                mem::transmute::<usize, fn(&ViolationInfo)>(f)
+            }))
+        }
+        _ => None,
+    }
+}
+
+/// Consults the policy for a violation.
+///
+/// Returns normally if the policy tolerates the violation.
+#[cold]
+#[track_caller]
+pub(crate) fn handle(
+    policy: Option<ViolationPolicy>,
+    default: ViolationPolicy,
+    info: ViolationInfo,
+) {
+    match policy.or_else(violation_policy).unwrap_or(default) {
+        ViolationPolicy::Panic => panic!("{}", info),
+        ViolationPolicy::Leak => {}
+        ViolationPolicy::Abort => {
+            eprintln!("fragile: {} (at {})", info, info.location);
+            process::abort();
+        }
+        ViolationPolicy::Log => eprintln!("fragile: {} (at {})", info, info.location),
+        ViolationPolicy::Callback(f) => f(&info),
+    }
+}
@@ -1,48 +1,201 @@
-pub struct Entry {
+//! Introspection of the thread local registry backing [`Sticky`](crate::Sticky).
+//!
+//! Every [`Sticky`](crate::Sticky) stores its value in a registry that belongs
+//! to the thread that created it.  The value stays there until the
+//! [`Sticky`](crate::Sticky) is dropped and the owning thread reclaims it, or
+//! until the owning thread shuts down.  The functions in this module can be
+//! used to find values that stay around for longer than expected.
+//!
+//! ```
+//! use fragile::{registry, Sticky};
+//!
+//! let before = registry::stats().entries();
+//! let val = Sticky::new(vec![1, 2, 3]);
+//! assert_eq!(registry::stats().entries(), before + 1);
+//! drop(val);
+//! assert_eq!(registry::stats().entries(), before);
+//! ```
+use std::any;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::mem;
+use std::panic::Location;
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::home::Home;
+
+pub(crate) struct Entry {
     /// The pointer to the object stored in the registry. This is a type-erased
     /// `Box<T>`.
-    pub ptr: *mut (),
+    pub(crate) ptr: *mut (),
     /// The function that can be called on the above pointer to drop the object
     /// and free its allocation.
-    pub drop: unsafe fn(*mut ()),
+    pub(crate) drop: unsafe fn(*mut ()),
+    /// The name of the stored type.
+    pub(crate) type_name: &'static str,
+    /// The size of the stored value.
+    pub(crate) size: usize,
+    /// Where the [`Sticky`](crate::Sticky) was created.
+    pub(crate) location: &'static Location<'static>,
+    /// The number of live borrows through weak handles.
+    pub(crate) pins: usize,
+    /// Set if the owning handle was dropped while the entry was pinned.
+    pub(crate) released: bool,
+    /// Shared with all weak handles to count them.
+    pub(crate) weak: Option<Arc<()>>,
+    /// The depth of the [`Scope`](crate::Scope) that destroys the entry, or
+    /// `0` if it lives until the thread shuts down.
+    pub(crate) depth: usize,
+    /// Set once the scope of the entry ended.  The value can no longer be
+    /// borrowed and is destroyed once no stack token can borrow it anymore.
+    pub(crate) ended: bool,
+    /// Set while the owning handle lends out the value exclusively.  Weak
+    /// handles cannot be upgraded in the meantime.
+    pub(crate) locked: bool,
+}
+
+impl Entry {
+    /// Creates an entry owning `value`.
+    pub(crate) fn new<T>(value: T, location: &'static Location<'static>) -> Entry {
+        Entry {
+            ptr: Box::into_raw(Box::new(value)).cast(),
+            drop: |ptr| {
+                let ptr = ptr.cast::<T>();
+                // SAFETY: This callback will only be called once, with the
+                // above pointer.
+                drop(unsafe { Box::from_raw(ptr) });
+            },
+            type_name: any::type_name::<T>(),
+            size: mem::size_of::<T>(),
+            location,
+            pins: 0,
+            released: false,
+            weak: None,
+            depth: 0,
+            ended: false,
+            locked: false,
+        }
+    }
+
+    fn has_weak(&self) -> bool {
+        self.weak
+            .as_ref()
+            .map_or(false, |x| Arc::strong_count(x) > 1)
+    }
 }
 
 #[cfg(feature = "slab")]
 mod slab_impl {
-    use std::cell::UnsafeCell;
-
     use super::Entry;
 
-    pub struct Registry(pub slab::Slab<Entry>);
+    pub struct Registry {
+        slab: slab::Slab<(u64, Entry)>,
+        generation: u64,
+    }
 
-    thread_local!(static REGISTRY: UnsafeCell<Registry> = UnsafeCell::new(Registry(slab::Slab::new())));
+    /// Slots of the slab are reused, so every id carries the generation of
+    /// the entry it was handed out for.  Generations increase with every
+    /// insert which also makes ids order by creation.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct ItemId {
+        generation: u64,
+        index: usize,
+    }
 
-    pub use usize as ItemId;
+    impl Registry {
+        pub fn new() -> Registry {
+            Registry {
+                slab: slab::Slab::new(),
+                generation: 0,
+            }
+        }
 
-    pub fn insert(entry: Entry) -> ItemId {
-        REGISTRY.with(|registry| unsafe { (*registry.get()).0.insert(entry) })
-    }
+        pub fn insert(&mut self, entry: Entry) -> ItemId {
+            self.generation += 1;
+            let generation = self.generation;
+            let index = self.slab.insert((generation, entry));
+            ItemId { generation, index }
+        }
 
-    pub fn with<R, F: FnOnce(&Entry) -> R>(item_id: ItemId, f: F) -> R {
-        REGISTRY.with(|registry| f(unsafe { &*registry.get() }.0.get(item_id).unwrap()))
-    }
+        pub fn get(&self, item_id: ItemId) -> Option<&Entry> {
+            match self.slab.get(item_id.index) {
+                Some(&(generation, ref entry)) if generation == item_id.generation => Some(entry),
+                _ => None,
+            }
+        }
+
+        pub fn get_mut(&mut self, item_id: ItemId) -> Option<&mut Entry> {
+            match self.slab.get_mut(item_id.index) {
+                Some(&mut (generation, ref mut entry)) if generation == item_id.generation => {
+                    Some(entry)
+                }
+                _ => None,
+            }
+        }
+
+        pub fn remove(&mut self, item_id: ItemId) -> Option<Entry> {
+            self.get(item_id)?;
+            Some(self.slab.remove(item_id.index).1)
+        }
+
+        pub fn len(&self) -> usize {
+            self.slab.len()
+        }
+
+        pub fn item_ids(&self) -> Vec<ItemId> {
+            self.slab
+                .iter()
+                .map(|(index, &(generation, _))| ItemId { generation, index })
+                .collect()
+        }
+
+        pub fn entries(&self) -> impl Iterator<Item = &Entry> + '_ {
+            self.slab.iter().map(|(_, (_, entry))| entry)
+        }
 
-    pub fn try_remove(item_id: ItemId) -> Option<Entry> {
-        REGISTRY.with(|registry| unsafe { (*registry.get()).0.try_remove(item_id) })
+        pub fn entries_mut(&mut self) -> impl Iterator<Item = &mut Entry> + '_ {
+            self.slab.iter_mut().map(|(_, (_, entry))| entry)
+        }
     }
 }
 
 #[cfg(not(feature = "slab"))]
 mod map_impl {
-    use std::cell::UnsafeCell;
+    use std::collections::HashMap;
+    use std::hash::{BuildHasherDefault, Hasher};
     use std::num::NonZeroUsize;
     use std::sync::atomic::{AtomicUsize, Ordering};
 
     use super::Entry;
 
-    pub struct Registry(pub std::collections::HashMap<NonZeroUsize, Entry>);
+    /// Item ids are sequential integers so there is no need for a DoS
+    /// resistant hasher.  A multiplicative hash spreads them over all bits.
+    #[derive(Default)]
+    pub struct ItemIdHasher(u64);
+
+    impl Hasher for ItemIdHasher {
+        fn finish(&self) -> u64 {
+            self.0
+        }
 
-    thread_local!(static REGISTRY: UnsafeCell<Registry> = UnsafeCell::new(Registry(Default::default())));
+        fn write(&mut self, bytes: &[u8]) {
+            for &byte in bytes {
+                self.write_u64(byte as u64);
+            }
+        }
+
+        fn write_u64(&mut self, i: u64) {
+            self.0 = (self.0.rotate_left(5) ^ i).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        }
+
+        fn write_usize(&mut self, i: usize) {
+            self.write_u64(i as u64);
+        }
+    }
+
+    pub struct Registry(HashMap<NonZeroUsize, Entry, BuildHasherDefault<ItemIdHasher>>);
 
     pub type ItemId = NonZeroUsize;
 
@@ -52,33 +205,754 @@ mod map_impl {
             .expect("more than usize::MAX items")
     }
 
-    pub fn insert(entry: Entry) -> ItemId {
-        let item_id = next_item_id();
-        REGISTRY.with(|registry| unsafe { (*registry.get()).0.insert(item_id, entry) });
-        item_id
-    }
+    impl Registry {
+        pub fn new() -> Registry {
+            Registry(Default::default())
+        }
 
-    pub fn with<R, F: FnOnce(&Entry) -> R>(item_id: ItemId, f: F) -> R {
-        REGISTRY.with(|registry| f(unsafe { &*registry.get() }.0.get(&item_id).unwrap()))
-    }
+        pub fn insert(&mut self, entry: Entry) -> ItemId {
+            let item_id = next_item_id();
+            self.0.insert(item_id, entry);
+            item_id
+        }
+
+        pub fn get(&self, item_id: ItemId) -> Option<&Entry> {
+            self.0.get(&item_id)
+        }
+
+        pub fn get_mut(&mut self, item_id: ItemId) -> Option<&mut Entry> {
+            self.0.get_mut(&item_id)
+        }
+
+        pub fn remove(&mut self, item_id: ItemId) -> Option<Entry> {
+            self.0.remove(&item_id)
+        }
+
+        pub fn len(&self) -> usize {
+            self.0.len()
+        }
+
+        pub fn item_ids(&self) -> Vec<ItemId> {
+            self.0.keys().copied().collect()
+        }
 
-    pub fn try_remove(item_id: ItemId) -> Option<Entry> {
-        REGISTRY.with(|registry| unsafe { (*registry.get()).0.remove(&item_id) })
+        pub fn entries(&self) -> impl Iterator<Item = &Entry> + '_ {
+            self.0.values()
+        }
+
+        pub fn entries_mut(&mut self) -> impl Iterator<Item = &mut Entry> + '_ {
+            self.0.values_mut()
+        }
     }
 }
 
 #[cfg(feature = "slab")]
-pub use self::slab_impl::*;
+pub(crate) use self::slab_impl::*;
 
 #[cfg(not(feature = "slab"))]
-pub use self::map_impl::*;
+pub(crate) use self::map_impl::*;
+
+/// Marks the registry of a thread that already shut down.
+const DESTROYED: *mut Registry = 1 as *mut Registry;
 
-impl Drop for Registry {
+// the registry is only ever reached through a raw pointer which keeps this
+// thread local free of a destructor.  That way it stays accessible while
+// other thread locals (and the values stored in the registry itself) are
+// destroyed.  The registry is torn down by the guard instead.
+thread_local!(static REGISTRY: Cell<*mut Registry> = Cell::new(ptr::null_mut()));
+thread_local!(static GUARD: RegistryGuard = RegistryGuard);
+thread_local!(static SHUTTING_DOWN: Cell<bool> = Cell::new(false));
+thread_local!(static SCOPE_DEPTH: Cell<usize> = Cell::new(0));
+// the number of live stack tokens per scope depth.  tokens created outside of
+// scopes are not counted as they cannot borrow values of scopes.
+thread_local!(static TOKENS: RefCell<Vec<usize>> = RefCell::new(Vec::new()));
+// set while entries of ended scopes wait for stack tokens to go away.
+thread_local!(static HAS_ENDED: Cell<bool> = Cell::new(false));
+
+struct RegistryGuard;
+
+impl Drop for RegistryGuard {
     fn drop(&mut self) {
-        for (_, value) in self.0.iter() {
-            // SAFETY: This function is only called once, and is called with the
-            // pointer it was created with.
-            unsafe { (value.drop)(value.ptr) };
+        SHUTTING_DOWN.try_with(|x| x.set(true)).ok();
+        if let Some(report) = exit_report() {
+            let snapshot = Snapshot::capture();
+            if !snapshot.entries.is_empty() {
+                report(&snapshot);
+            }
         }
+
+        // destructors of stored values can remove, access and even create
+        // other entries.  Entries are therefore removed one at a time and
+        // destroyed while the registry is not borrowed, until none are left.
+        // Newer entries go first as they are more likely to depend on older
+        // ones than the other way round.
+        while let Some(mut item_ids) = with_registry(|registry| registry.item_ids()) {
+            if item_ids.is_empty() {
+                break;
+            }
+            item_ids.sort_unstable_by(|a, b| b.cmp(a));
+            for item_id in item_ids {
+                if let Some(entry) = try_remove(item_id) {
+                    // SAFETY: the entry was removed so this is the only
+                    // call for this pointer.
+                    unsafe { (entry.drop)(entry.ptr) };
+                }
+            }
+        }
+
+        REGISTRY.with(|registry| {
+            let ptr = registry.replace(DESTROYED);
+            if !ptr.is_null() && ptr != DESTROYED {
+                // SAFETY: the pointer was created by `Box::into_raw` and is
+                // no longer reachable.
+                drop(unsafe { Box::from_raw(ptr) });
+            }
+        });
     }
 }
+
+/// Invokes `f` with the registry of the current thread.
+///
+/// Returns `None` if the thread has no registry or its registry was already
+/// destroyed.  `f` must not call back into code outside of this module as
+/// the registry is mutably borrowed while it runs.
+fn with_registry<R, F: FnOnce(&mut Registry) -> R>(f: F) -> Option<R> {
+    REGISTRY
+        .try_with(|registry| {
+            let ptr = registry.get();
+            if ptr.is_null() || ptr == DESTROYED {
+                None
+            } else {
+                // SAFETY: the registry is only borrowed within this module
+                // and never across calls into foreign code.
+                Some(f(unsafe { &mut *ptr }))
+            }
+        })
+        .ok()
+        .flatten()
+}
+
+/// Stores an entry in the registry of the current thread.
+///
+/// The entry is destroyed when the innermost [`Scope`](crate::Scope) ends.
+/// Hands the entry back if the registry of the current thread was already
+/// destroyed.
+pub(crate) fn insert(mut entry: Entry) -> Result<ItemId, Entry> {
+    entry.depth = scope_depth();
+    insert_unscoped(entry)
+}
+
+/// Stores an entry in the registry of the current thread that lives until
+/// the thread shuts down, even if it is created within a scope.
+pub(crate) fn insert_unscoped(entry: Entry) -> Result<ItemId, Entry> {
+    let ptr = REGISTRY.with(|registry| {
+        if registry.get().is_null() {
+            // the guard is registered before the registry exists so that the
+            // registry is guaranteed to be torn down.
+            if GUARD.try_with(|_| ()).is_ok() {
+                registry.set(Box::into_raw(Box::new(Registry::new())));
+            } else {
+                registry.set(DESTROYED);
+            }
+        }
+        registry.get()
+    });
+    if ptr == DESTROYED {
+        return Err(entry);
+    }
+    // SAFETY: see `with_registry`.
+    Ok(unsafe { (*ptr).insert(entry) })
+}
+
+/// Why a value could not be found in the registry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum LookupError {
+    /// The registry of the current thread was already destroyed.
+    Destroyed,
+    /// The id does not refer to a live entry (anymore).
+    Stale,
+    /// The value is borrowed through a weak handle or exclusively by its
+    /// owning handle.
+    Pinned,
+    /// Weak handles to the value exist.
+    Shared,
+    /// The value belongs to a scope that was entered after the stack token
+    /// was created.
+    OuterToken,
+}
+
+/// Returns the pointer to the value stored under the given id.
+///
+/// `depth` is the scope depth of the stack token the value is borrowed with.
+pub(crate) fn get(item_id: ItemId, depth: usize) -> Result<*mut (), LookupError> {
+    with_registry(|registry| match registry.get(item_id) {
+        None => Err(LookupError::Stale),
+        Some(entry) if entry.ended => Err(LookupError::Stale),
+        Some(entry) if entry.depth > depth => Err(LookupError::OuterToken),
+        Some(entry) => Ok(entry.ptr),
+    })
+    .unwrap_or(Err(LookupError::Destroyed))
+}
+
+/// Returns the pointer to the value stored under the given id for exclusive
+/// access.
+pub(crate) fn get_exclusive(item_id: ItemId, depth: usize) -> Result<*mut (), LookupError> {
+    with_registry(|registry| match registry.get(item_id) {
+        None => Err(LookupError::Stale),
+        Some(entry) if entry.ended => Err(LookupError::Stale),
+        Some(entry) if entry.depth > depth => Err(LookupError::OuterToken),
+        Some(entry) if entry.pins > 0 => Err(LookupError::Pinned),
+        Some(entry) if entry.has_weak() => Err(LookupError::Shared),
+        Some(entry) => Ok(entry.ptr),
+    })
+    .unwrap_or(Err(LookupError::Destroyed))
+}
+
+/// Locks an entry for exclusive access by its owning handle.
+///
+/// Unlike [`get_exclusive`] this does not fail if weak handles exist, they
+/// just cannot be upgraded until the returned [`Lock`] is dropped.
+pub(crate) fn lock(item_id: ItemId, depth: usize) -> Result<(*mut (), Lock), LookupError> {
+    with_registry(|registry| match registry.get_mut(item_id) {
+        None => Err(LookupError::Stale),
+        Some(entry) if entry.ended => Err(LookupError::Stale),
+        Some(entry) if entry.depth > depth => Err(LookupError::OuterToken),
+        Some(entry) if entry.pins > 0 => Err(LookupError::Pinned),
+        Some(entry) => {
+            entry.locked = true;
+            Ok((entry.ptr, Lock { item_id }))
+        }
+    })
+    .unwrap_or(Err(LookupError::Destroyed))
+}
+
+/// Unlocks an entry locked with [`lock`] when dropped.
+pub(crate) struct Lock {
+    item_id: ItemId,
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        with_registry(|registry| {
+            if let Some(entry) = registry.get_mut(self.item_id) {
+                entry.locked = false;
+            }
+        });
+    }
+}
+
+/// Removes an entry for the owning handle so its value can be moved out.
+pub(crate) fn take(item_id: ItemId) -> Result<Entry, LookupError> {
+    with_registry(|registry| match registry.get(item_id) {
+        None => Err(LookupError::Stale),
+        Some(entry) if entry.ended => Err(LookupError::Stale),
+        Some(entry) if entry.pins > 0 => Err(LookupError::Pinned),
+        Some(_) => registry.remove(item_id).ok_or(LookupError::Stale),
+    })
+    .unwrap_or(Err(LookupError::Destroyed))
+}
+
+/// Returns the token shared by the weak handles of an entry.
+pub(crate) fn downgrade(item_id: ItemId) -> Result<Arc<()>, LookupError> {
+    with_registry(|registry| match registry.get_mut(item_id) {
+        None => Err(LookupError::Stale),
+        Some(entry) if entry.ended => Err(LookupError::Stale),
+        Some(entry) => Ok(entry.weak.get_or_insert_with(Default::default).clone()),
+    })
+    .unwrap_or(Err(LookupError::Destroyed))
+}
+
+/// Removes an entry because its owning handle was dropped.
+///
+/// If the entry is pinned, the removal is deferred until the last pin is
+/// released.  Returns the entry if it must be destroyed by the caller.
+pub(crate) fn release(item_id: ItemId) -> Option<Entry> {
+    with_registry(|registry| match registry.get_mut(item_id) {
+        Some(entry) if entry.pins > 0 => {
+            entry.released = true;
+            None
+        }
+        Some(_) => registry.remove(item_id),
+        None => None,
+    })
+    .flatten()
+}
+
+/// Pins an entry that was not released yet and returns the pointer to its
+/// value.
+pub(crate) fn pin(item_id: ItemId, depth: usize) -> Result<*mut (), LookupError> {
+    with_registry(|registry| match registry.get_mut(item_id) {
+        Some(entry) if entry.released || entry.ended => Err(LookupError::Stale),
+        Some(entry) if entry.depth > depth => Err(LookupError::OuterToken),
+        Some(entry) if entry.locked => Err(LookupError::Pinned),
+        Some(entry) => {
+            entry.pins += 1;
+            Ok(entry.ptr)
+        }
+        None => Err(LookupError::Stale),
+    })
+    .unwrap_or(Err(LookupError::Destroyed))
+}
+
+/// Releases a pin.  Returns the entry if it must be destroyed by the caller.
+pub(crate) fn unpin(item_id: ItemId) -> Option<Entry> {
+    with_registry(|registry| match registry.get_mut(item_id) {
+        Some(entry) => {
+            entry.pins -= 1;
+            if entry.pins == 0 && entry.released {
+                registry.remove(item_id)
+            } else {
+                None
+            }
+        }
+        None => None,
+    })
+    .flatten()
+}
+
+/// Removes an entry from the registry of the current thread.
+///
+/// The caller is responsible for invoking the destructor of the entry.
+pub(crate) fn try_remove(item_id: ItemId) -> Option<Entry> {
+    with_registry(|registry| registry.remove(item_id)).flatten()
+}
+
+/// Returns the depth of the innermost [`Scope`](crate::Scope) of the current
+/// thread.
+pub(crate) fn scope_depth() -> usize {
+    SCOPE_DEPTH.try_with(|x| x.get()).unwrap_or(0)
+}
+
+/// Returns `true` while the registry of the current thread is torn down.
+pub(crate) fn is_shutting_down() -> bool {
+    SHUTTING_DOWN.try_with(|x| x.get()).unwrap_or(true)
+}
+
+/// Enters a new scope and returns its depth.
+pub(crate) fn enter_scope() -> usize {
+    SCOPE_DEPTH.with(|x| {
+        x.set(x.get() + 1);
+        x.get()
+    })
+}
+
+/// Returns the number of entries that are destroyed when the scope at
+/// `depth` ends.
+pub(crate) fn scope_entries(depth: usize) -> usize {
+    with_registry(|registry| {
+        registry
+            .entries()
+            .filter(|x| !x.ended && x.depth >= depth)
+            .count()
+    })
+    .unwrap_or(0)
+}
+
+/// Registers a new stack token and returns the scope depth it belongs to.
+pub(crate) fn acquire_token() -> usize {
+    let depth = scope_depth();
+    if depth > 0 {
+        TOKENS
+            .try_with(|tokens| {
+                let mut tokens = tokens.borrow_mut();
+                if tokens.len() <= depth {
+                    tokens.resize(depth + 1, 0);
+                }
+                tokens[depth] += 1;
+            })
+            .ok();
+    }
+    depth
+}
+
+/// Unregisters a stack token of a scope.
+pub(crate) fn release_token(depth: usize) {
+    TOKENS
+        .try_with(|tokens| {
+            if let Some(count) = tokens.borrow_mut().get_mut(depth) {
+                *count = count.saturating_sub(1);
+            }
+        })
+        .ok();
+    if HAS_ENDED.try_with(|x| x.get()).unwrap_or(false) {
+        destroy_ended();
+    }
+}
+
+/// Destroys the entries of ended scopes that no live stack token can borrow.
+fn destroy_ended() {
+    // values can only be borrowed with tokens of their own or deeper scopes,
+    // so everything deeper than the deepest live token is safe to destroy.
+    let safe_depth = match TOKENS.try_with(|tokens| {
+        let tokens = tokens.borrow();
+        tokens.iter().rposition(|&x| x > 0).map_or(1, |x| x + 1)
+    }) {
+        Ok(safe_depth) => safe_depth,
+        Err(_) => return,
+    };
+    let (mut item_ids, pending) = with_registry(|registry| {
+        let mut item_ids = Vec::new();
+        let mut pending = false;
+        for item_id in registry.item_ids() {
+            match registry.get(item_id) {
+                Some(entry) if !entry.ended => {}
+                Some(entry) if entry.depth >= safe_depth && entry.pins == 0 => {
+                    item_ids.push(item_id)
+                }
+                _ => pending = true,
+            }
+        }
+        (item_ids, pending)
+    })
+    .unwrap_or_default();
+    HAS_ENDED.try_with(|x| x.set(pending)).ok();
+
+    item_ids.sort_unstable_by(|a, b| b.cmp(a));
+    for item_id in item_ids {
+        if let Some(entry) = try_remove(item_id) {
+            // SAFETY: the entry was removed so this is the only call for
+            // this pointer.
+            unsafe { (entry.drop)(entry.ptr) };
+        }
+    }
+}
+
+/// Ends the scope at `depth` and leaves it.
+///
+/// The entries of the scope are destroyed right away unless stack tokens of
+/// the scope outlive it, for instance in a future that is returned from the
+/// scope.  As such tokens might still borrow values, the entries are only
+/// marked as ended and destroyed once the last of those tokens is gone or
+/// their handle is dropped.
+pub(crate) fn leave_scope(depth: usize) {
+    // destructors can create new entries in the scope which end as well.
+    loop {
+        let ended = with_registry(|registry| {
+            let mut ended = 0;
+            for entry in registry.entries_mut() {
+                if !entry.ended && entry.depth >= depth {
+                    entry.ended = true;
+                    ended += 1;
+                }
+            }
+            ended
+        })
+        .unwrap_or(0);
+        if ended == 0 {
+            break;
+        }
+        HAS_ENDED.try_with(|x| x.set(true)).ok();
+        destroy_ended();
+    }
+    SCOPE_DEPTH.try_with(|x| x.set(depth - 1)).ok();
+}
+
+thread_local!(static REMOTE_DROPS: Cell<usize> = Cell::new(0));
+
+static EXIT_REPORT: AtomicUsize = AtomicUsize::new(0);
+
+/// Records that an entry of a [`Sticky`](crate::Sticky) dropped on a foreign
+/// thread was reclaimed.
+pub(crate) fn record_remote_drop() {
+    REMOTE_DROPS.try_with(|x| x.set(x.get() + 1)).ok();
+}
+
+/// Summary statistics of the registry of a thread.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    entries: usize,
+    bytes: usize,
+    remote_drops: usize,
+    pending_remote_drops: usize,
+}
+
+impl Stats {
+    /// The number of values currently stored in the registry.
+    pub fn entries(&self) -> usize {
+        self.entries
+    }
+
+    /// The combined size of all values stored in the registry.
+    ///
+    /// This is the shallow size of the values as reported by
+    /// [`mem::size_of`], heap allocations owned by the values are not
+    /// included.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// The number of values that were dropped on a foreign thread and that
+    /// were reclaimed by this thread so far.
+    pub fn remote_drops(&self) -> usize {
+        self.remote_drops
+    }
+
+    /// The number of values that were dropped on a foreign thread and that
+    /// are still waiting to be reclaimed by this thread.
+    ///
+    /// This includes values of [`Fragile`](crate::Fragile) wrappers created
+    /// with [`Fragile::new_deferred`](crate::Fragile::new_deferred).
+    pub fn pending_remote_drops(&self) -> usize {
+        self.pending_remote_drops
+    }
+}
+
+/// The values of one type stored in the registry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeStats {
+    type_name: &'static str,
+    count: usize,
+    bytes: usize,
+}
+
+impl TypeStats {
+    /// The name of the type.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// The number of values of this type.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// The combined size of all values of this type.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+}
+
+/// A single value stored in the registry.
+#[derive(Clone, Debug)]
+pub struct EntryInfo {
+    type_name: &'static str,
+    size: usize,
+    location: &'static Location<'static>,
+}
+
+impl EntryInfo {
+    /// The name of the type of the value.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// The size of the value.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The location where the [`Sticky`](crate::Sticky) was created.
+    pub fn location(&self) -> &'static Location<'static> {
+        self.location
+    }
+}
+
+/// A detailed view of the registry of a thread.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    stats: Stats,
+    types: Vec<TypeStats>,
+    entries: Vec<EntryInfo>,
+}
+
+impl Snapshot {
+    fn capture() -> Snapshot {
+        let entries = with_registry(|registry| {
+            registry
+                .entries()
+                .map(|entry| EntryInfo {
+                    type_name: entry.type_name,
+                    size: entry.size,
+                    location: entry.location,
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+        let mut types = HashMap::<&'static str, TypeStats>::new();
+        for entry in &entries {
+            let stats = types.entry(entry.type_name).or_insert(TypeStats {
+                type_name: entry.type_name,
+                count: 0,
+                bytes: 0,
+            });
+            stats.count += 1;
+            stats.bytes += entry.size;
+        }
+        let mut types: Vec<_> = types.into_values().collect();
+        types.sort_by(|a, b| {
+            b.bytes
+                .cmp(&a.bytes)
+                .then(b.count.cmp(&a.count))
+                .then(a.type_name.cmp(b.type_name))
+        });
+
+        Snapshot {
+            stats: Stats {
+                entries: entries.len(),
+                bytes: entries.iter().map(|x| x.size).sum(),
+                ..stats_without_entries()
+            },
+            types,
+            entries,
+        }
+    }
+
+    /// The summary statistics.
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    /// The stored values grouped by type, largest first.
+    pub fn types(&self) -> &[TypeStats] {
+        &self.types
+    }
+
+    /// All values stored in the registry.
+    pub fn entries(&self) -> &[EntryInfo] {
+        &self.entries
+    }
+}
+
+/// Returns summary statistics of the registry of the current thread.
+pub fn stats() -> Stats {
+    let (entries, bytes) = with_registry(|registry| {
+        (
+            registry.len(),
+            registry.entries().map(|entry| entry.size).sum(),
+        )
+    })
+    .unwrap_or_default();
+    Stats {
+        entries,
+        bytes,
+        ..stats_without_entries()
+    }
+}
+
+fn stats_without_entries() -> Stats {
+    Stats {
+        entries: 0,
+        bytes: 0,
+        remote_drops: REMOTE_DROPS.try_with(|x| x.get()).unwrap_or(0),
+        pending_remote_drops: Home::buried_on_current(),
+    }
+}
+
+/// Returns a detailed view of the registry of the current thread.
+pub fn snapshot() -> Snapshot {
+    Snapshot::capture()
+}
+
+/// Installs a function that reports the values left behind by a thread.
+///
+/// When a thread shuts down while its registry still holds values, the
+/// function is invoked on that thread with a [`Snapshot`] of those values
+/// right before they are destroyed.  These are values whose
+/// [`Sticky`](crate::Sticky) was sent to another thread and not dropped yet,
+/// was forgotten, or was dropped on a foreign thread after the owning thread
+/// last reclaimed its values.  Passing `None` removes the function.
+///
+/// ```
+/// fn report(snapshot: &fragile::registry::Snapshot) {
+///     for ty in snapshot.types() {
+///         eprintln!("leaked {} x {} ({} bytes)", ty.count(), ty.type_name(), ty.bytes());
+///     }
+/// }
+///
+/// fragile::registry::set_exit_report(Some(report));
+/// ```
+pub fn set_exit_report(report: Option<fn(&Snapshot)>) {
+    EXIT_REPORT.store(report.map_or(0, |f| f as usize), Ordering::Release);
+}
+
+fn exit_report() -> Option<fn(&Snapshot)> {
+    match EXIT_REPORT.load(Ordering::Acquire) {
+        0 => None,
+        // SAFETY: only function pointers of this type are ever stored.
+        f => Some(unsafe { mem::transmute::<usize, fn(&Snapshot)>(f) }),
+    }
+}
+
+#[test]
+fn test_stats() {
+    use crate::Sticky;
+
+    let before = stats();
+    let a = Sticky::new(42u64);
+    let b = Sticky::new(23u64);
+    let c = Sticky::new(true);
+    let after = stats();
+    assert_eq!(after.entries(), before.entries() + 3);
+    assert_eq!(after.bytes(), before.bytes() + 17);
+
+    let snapshot = snapshot();
+    let ty = snapshot
+        .types()
+        .iter()
+        .find(|x| x.type_name() == "u64")
+        .unwrap();
+    assert_eq!(ty.count(), 2);
+    assert_eq!(ty.bytes(), 16);
+    let entry = snapshot
+        .entries()
+        .iter()
+        .find(|x| x.type_name() == "bool")
+        .unwrap();
+    assert_eq!(entry.size(), 1);
+    assert_eq!(entry.location().file(), file!());
+
+    drop((a, b, c));
+    assert_eq!(stats(), before);
+}
+
+#[test]
+fn test_remote_drops() {
+    use crate::Sticky;
+    use std::thread;
+
+    let val = Sticky::new(vec![1, 2, 3]);
+    let before = stats();
+    thread::spawn(move || drop(val)).join().unwrap();
+    let pending = stats();
+    assert_eq!(pending.entries(), before.entries());
+    assert_eq!(pending.pending_remote_drops(), 1);
+
+    crate::pump();
+    let after = stats();
+    assert_eq!(after.entries(), before.entries() - 1);
+    assert_eq!(after.pending_remote_drops(), 0);
+    assert_eq!(after.remote_drops(), before.remote_drops() + 1);
+}
+
+#[test]
+fn test_stale_item_id() {
+    fn entry(value: u32) -> Entry {
+        Entry {
+            ptr: Box::into_raw(Box::new(value)).cast(),
+            drop: |ptr| drop(unsafe { Box::from_raw(ptr.cast::<u32>()) }),
+            type_name: "u32",
+            size: 4,
+            location: Location::caller(),
+            pins: 0,
+            released: false,
+            weak: None,
+            depth: 0,
+            ended: false,
+            locked: false,
+        }
+    }
+
+    let old = insert(entry(1)).ok().unwrap();
+    let removed = try_remove(old).unwrap();
+    unsafe { (removed.drop)(removed.ptr) };
+    assert!(try_remove(old).is_none());
+
+    // the slot of the removed entry is reused by the slab backend.
+    let new = insert(entry(2)).ok().unwrap();
+    assert!(new > old);
+    assert_eq!(get(old, 0), Err(LookupError::Stale));
+    assert_eq!(unsafe { *get(new, 0).unwrap().cast::<u32>() }, 2);
+    let removed = try_remove(new).unwrap();
+    unsafe { (removed.drop)(removed.ptr) };
+}
@@ -0,0 +1,225 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+use std::thread;
+
+use crate::fragile::Fragile;
+use crate::home::{self, Home, HomeTask};
+
+/// A handle to a value that lives on a dedicated thread.
+///
+/// [`Remote::spawn`] starts a thread, constructs the value there and keeps
+/// pumping the [`Home`] of that thread for as long as handles to the value
+/// exist.  The handles are `Send`, `Sync` and `Clone` and can be used to run
+/// closures against the value on its thread with [`call`](Self::call) or
+/// [`call_async`](Self::call_async).  Once the last handle is dropped, the
+/// value is dropped on its thread and the thread shuts down.
+///
+/// ```
+/// use std::rc::Rc;
+/// use std::thread;
+/// use fragile::Remote;
+///
+/// let remote = Remote::spawn(|| Rc::new(vec![1, 2, 3]));
+/// let other = remote.clone();
+/// thread::spawn(move || {
+///     other.call(|value| Rc::make_mut(value).push(4));
+/// })
+/// .join()
+/// .unwrap();
+/// assert_eq!(remote.call(|value| value.len()), 4);
+/// ```
+pub struct Remote<T: 'static> {
+    inner: Arc<RemoteInner<T>>,
+}
+
+struct RemoteInner<T: 'static> {
+    // only `None` while the inner value is dropped.
+    value: Option<Fragile<RefCell<T>>>,
+    home: Home,
+    stop: Arc<AtomicBool>,
+}
+
+impl<T: 'static> RemoteInner<T> {
+    fn value(&self) -> &RefCell<T> {
+        match self.value {
+            Some(ref value) => value.get(),
+            None => unreachable!(),
+        }
+    }
+}
+
+impl<T: 'static> Drop for RemoteInner<T> {
+    fn drop(&mut self) {
+        // the value is dropped by the last job of the thread which then shuts
+        // down.  if the thread is already gone, the value is leaked.
+        let value = self.value.take();
+        let stop = self.stop.clone();
+        self.home.push(Box::new(move || {
+            drop(value);
+            stop.store(true, Ordering::SeqCst);
+        }));
+    }
+}
+
+impl<T: 'static> Remote<T> {
+    /// Starts a thread and constructs the value on it with `f`.
+    ///
+    /// This blocks until the value is constructed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the thread cannot be spawned.  If `f` panics, the panic is
+    /// propagated to the caller.
+    pub fn spawn<F>(f: F) -> Remote<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let (tx, rx) = channel();
+        let handle = thread::Builder::new()
+            .name("fragile-remote".into())
+            .spawn(move || {
+                let stop = Arc::new(AtomicBool::new(false));
+                let value = Fragile::new_deferred(RefCell::new(f()));
+                if tx.send((value, Home::current(), stop.clone())).is_err() {
+                    return;
+                }
+                while !stop.load(Ordering::SeqCst) {
+                    // a panicking call only abandons its own task.
+                    if let Ok(0) = panic::catch_unwind(AssertUnwindSafe(home::pump)) {
+                        thread::park();
+                    }
+                }
+            })
+            .expect("failed to spawn remote thread");
+
+        match rx.recv() {
+            Ok((value, home, stop)) => Remote {
+                inner: Arc::new(RemoteInner {
+                    value: Some(value),
+                    home,
+                    stop,
+                }),
+            },
+            Err(_) => match handle.join() {
+                Err(panic) => panic::resume_unwind(panic),
+                Ok(()) => unreachable!(),
+            },
+        }
+    }
+
+    /// Returns the [`Home`] of the thread that holds the value.
+    pub fn home(&self) -> &Home {
+        &self.inner.home
+    }
+
+    /// Runs `f` with the value on its thread and blocks until it returns.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` panicked or if this is invoked from within another call
+    /// on the same value.
+    pub fn call<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        match self.call_async(f).wait() {
+            Ok(rv) => rv,
+            Err(_) => panic!("remote call panicked"),
+        }
+    }
+
+    /// Queues `f` to run with the value on its thread.
+    ///
+    /// The returned [`HomeTask`] resolves to the return value of `f`, or to
+    /// an error if `f` panicked.  With the `future` feature it can be awaited.
+    pub fn call_async<F, R>(&self, f: F) -> HomeTask<R>
+    where
+        F: FnOnce(&mut T) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let inner = self.inner.clone();
+        self.inner
+            .home
+            .spawn(move || f(&mut inner.value().borrow_mut()))
+    }
+}
+
+impl<T: 'static> Clone for Remote<T> {
+    #[inline]
+    fn clone(&self) -> Remote<T> {
+        Remote {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T: 'static> fmt::Debug for Remote<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Remote")
+            .field("home", &self.inner.home)
+            .finish()
+    }
+}
+
+#[test]
+fn test_call() {
+    use std::rc::Rc;
+
+    let remote = Remote::spawn(|| Rc::new(thread::current().id()));
+    let owner = remote.call(|value| **value);
+    assert_eq!(owner, remote.home().thread_id());
+    assert_ne!(owner, thread::current().id());
+    assert_eq!(remote.home().thread_name(), Some("fragile-remote"));
+}
+
+#[cfg(feature = "future")]
+#[test]
+fn test_call_async() {
+    let remote = Remote::spawn(|| vec![1, 2, 3]);
+    let task = remote.call_async(|value| {
+        value.push(4);
+        value.len()
+    });
+    assert_eq!(futures_executor::block_on(task).unwrap(), 4);
+}
+
+#[test]
+fn test_shutdown() {
+    use std::sync::mpsc::Sender;
+    struct X(Sender<thread::ThreadId>);
+    impl Drop for X {
+        fn drop(&mut self) {
+            self.0.send(thread::current().id()).unwrap();
+        }
+    }
+
+    let (tx, rx) = channel();
+    let remote = Remote::spawn(move || X(tx));
+    let owner = remote.home().thread_id();
+    let other = remote.clone();
+    drop(remote);
+    assert!(rx.try_recv().is_err());
+    thread::spawn(move || drop(other)).join().unwrap();
+    assert_eq!(rx.recv().unwrap(), owner);
+}
+
+#[test]
+fn test_panicking_call() {
+    let remote = Remote::spawn(|| 42);
+    let other = remote.clone();
+    assert!(thread::spawn(move || other.call(|_| panic!("boom")))
+        .join()
+        .is_err());
+    assert_eq!(remote.call(|value| *value), 42);
+}
+
+#[test]
+fn test_panicking_constructor() {
+    let rv = thread::spawn(|| Remote::<i32>::spawn(|| panic!("boom"))).join();
+    assert!(rv.is_err());
+}
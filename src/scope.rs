@@ -0,0 +1,215 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::registry;
+
+/// A scope of the registry of the current thread.
+///
+/// See [`scope`] for more information.
+pub struct Scope {
+    depth: usize,
+    // scopes belong to the thread that entered them.
+    _marker: PhantomData<*const ()>,
+}
+
+impl Scope {
+    /// Returns the number of values that are destroyed when the scope ends.
+    ///
+    /// This includes the values of scopes nested in this one.
+    pub fn entries(&self) -> usize {
+        registry::scope_entries(self.depth)
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        registry::leave_scope(self.depth);
+    }
+}
+
+impl fmt::Debug for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Scope")
+            .field("depth", &self.depth)
+            .field("entries", &self.entries())
+            .finish()
+    }
+}
+
+/// Runs `f` in a new scope of the registry of the current thread.
+///
+/// All values that [`Sticky`](crate::Sticky), [`SemiSticky`](crate::SemiSticky)
+/// and [`StickyKey`](crate::StickyKey) store in the registry while `f` runs are
+/// destroyed when it returns or panics, regardless of where their handles
+/// are.  This allows long running threads such as event loops to reclaim
+/// values that were leaked or sent to other threads and never dropped, for
+/// instance once per request.  Values of a [`PerThread`](crate::PerThread)
+/// are not affected.
+///
+/// Accessing a value after its scope ended fails with
+/// [`ScopeEnded`](crate::AccessErrorReason::ScopeEnded).  Scopes can be
+/// nested.  Values of a scope can only be borrowed with
+/// [`StackToken`](crate::StackToken)s created within that scope, otherwise
+/// borrowing fails with [`OuterToken`](crate::AccessErrorReason::OuterToken).
+///
+/// Stack tokens of a scope can outlive it, for instance when a future that
+/// holds a borrow across an `.await` is returned from the scope.  Values
+/// such a token might borrow are not destroyed until the last token of the
+/// scope (or a nested one) is gone or their handle is dropped.  They can no
+/// longer be borrowed anew in the meantime.
+///
+/// ```
+/// use std::thread;
+/// use fragile::{AccessErrorReason, Sticky};
+///
+/// let val = fragile::scope(|_| {
+///     let val = Sticky::new(vec![1, 2, 3]);
+///     // the handle is sent to another thread which never drops it.
+///     let leaked = Sticky::new(vec![4, 5, 6]);
+///     thread::spawn(move || std::mem::forget(leaked)).join().unwrap();
+///     val
+/// });
+///
+/// // both values were destroyed at the end of the scope.
+/// fragile::stack_token!(tok);
+/// let err = val.try_get(tok).unwrap_err();
+/// assert_eq!(err.reason(), AccessErrorReason::ScopeEnded);
+/// ```
+pub fn scope<R, F: FnOnce(&Scope) -> R>(f: F) -> R {
+    let scope = Scope {
+        depth: registry::enter_scope(),
+        _marker: PhantomData,
+    };
+    f(&scope)
+}
+
+#[test]
+fn test_scope() {
+    use crate::{AccessErrorReason, PerThread, Sticky};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    let value = Rc::new(());
+    let per_thread = PerThread::new(|| Cell::new(0));
+    let before = registry::stats().entries();
+    let outer = scope(|outer| {
+        let a = Sticky::new(value.clone());
+        scope(|inner| {
+            std::mem::forget(Sticky::new(value.clone()));
+            assert_eq!(inner.entries(), 1);
+            assert_eq!(outer.entries(), 2);
+        });
+        assert_eq!(outer.entries(), 1);
+        assert_eq!(Rc::strong_count(&value), 2);
+        per_thread.with(|x| x.set(42));
+        a
+    });
+    assert_eq!(Rc::strong_count(&value), 1);
+    assert_eq!(registry::stats().entries(), before + 1);
+    assert_eq!(per_thread.with(|x| x.get()), 42);
+
+    crate::stack_token!(tok);
+    let err = outer.try_get(tok).unwrap_err();
+    assert_eq!(err.reason(), AccessErrorReason::ScopeEnded);
+    drop(outer);
+}
+
+#[test]
+fn test_outer_token() {
+    use crate::{AccessErrorReason, Sticky};
+
+    crate::stack_token!(outer);
+    scope(|_| {
+        let val = Sticky::new(42);
+        let err = val.try_get(outer).unwrap_err();
+        assert_eq!(err.reason(), AccessErrorReason::OuterToken);
+        crate::stack_token!(inner);
+        assert_eq!(*val.get(inner), 42);
+        let weak = val.downgrade();
+        assert!(weak.upgrade(outer).is_none());
+        assert_eq!(*weak.upgrade(inner).unwrap(), 42);
+    });
+}
+
+#[test]
+fn test_panic() {
+    use crate::Sticky;
+    use std::panic;
+    use std::rc::Rc;
+
+    let value = Rc::new(());
+    let rv = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        scope(|_| {
+            std::mem::forget(Sticky::new(value.clone()));
+            panic!("boom");
+        })
+    }));
+    assert!(rv.is_err());
+    assert_eq!(Rc::strong_count(&value), 1);
+    assert_eq!(registry::scope_depth(), 0);
+}
+
+#[test]
+fn test_borrow_outlives_scope() {
+    use crate::{AccessErrorReason, Sticky};
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::rc::Rc;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+
+    struct Noop;
+    impl Wake for Noop {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    // yields once after waking itself.
+    struct YieldNow(bool);
+    impl Future for YieldNow {
+        type Output = ();
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                Poll::Ready(())
+            } else {
+                self.0 = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+
+    let waker = Waker::from(Arc::new(Noop));
+    let mut cx = Context::from_waker(&waker);
+    let before = registry::stats().entries();
+    let value = Rc::new(());
+
+    let mut future = scope(|_| {
+        let value = value.clone();
+        let mut future = Box::pin(async move {
+            crate::stack_token!(tok);
+            let s = Sticky::new(String::from("hello"));
+            let r = s.get(tok);
+            // the handle of this value is never dropped.
+            let leaked = Sticky::new(value);
+            let _ = leaked.get(tok);
+            std::mem::forget(leaked);
+            YieldNow(false).await;
+            // the value cannot be borrowed anew once the scope ended.
+            let err = s.try_get(tok).unwrap_err();
+            assert_eq!(err.reason(), AccessErrorReason::ScopeEnded);
+            r.clone()
+        });
+        assert!(future.as_mut().poll(&mut cx).is_pending());
+        future
+    });
+
+    // the token in the future keeps the values of the scope alive.
+    assert_eq!(Rc::strong_count(&value), 2);
+    match future.as_mut().poll(&mut cx) {
+        Poll::Ready(rv) => assert_eq!(rv, "hello"),
+        Poll::Pending => panic!("expected the future to complete"),
+    }
+    drop(future);
+    assert_eq!(Rc::strong_count(&value), 1);
+    assert_eq!(registry::stats().entries(), before);
+}
@@ -1,27 +1,492 @@
 use std::cmp;
+use std::convert::TryFrom;
+use std::error;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io;
 use std::mem;
 
-use crate::errors::InvalidThreadAccess;
+use crate::errors::{IntoInnerError, InvalidThreadAccess, WrapperKind};
 use crate::fragile::Fragile;
+use crate::home::{self, Home, HomeTask};
+use crate::policy::{ViolationKind, ViolationPolicy};
 use crate::sticky::Sticky;
 use crate::StackToken;
 
-enum SemiStickyImpl<T: 'static> {
+mod private {
+    pub trait Sealed {}
+}
+
+/// How a [`SemiSticky`] stores its value.
+///
+/// See [`SemiSticky::with_strategy`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Strategy {
+    /// Uses [`Sticky`](Self::Sticky) if the type needs to be dropped and
+    /// [`Fragile`](Self::Fragile) otherwise.
+    Auto,
+    /// Stores the value in a [`Fragile`] like [`Fragile::new`].  Dropping a
+    /// value that needs to be dropped on a foreign thread consults the
+    /// violation policy which panics by default.
+    Fragile,
+    /// Stores the value in the thread local registry like [`Sticky::new`].
+    Sticky,
+    /// Stores the value in a [`Fragile`] like [`Fragile::new_deferred`] which
+    /// hands the value back to its home if it is dropped on a foreign thread.
+    Deferred,
+}
+
+impl Default for Strategy {
+    fn default() -> Strategy {
+        Strategy::Auto
+    }
+}
+
+/// Selects how a [`SemiSticky`] stores its value.
+///
+/// By default a [`SemiSticky`] picks a [`Strategy`] when the value is wrapped
+/// and dispatches on it for every access ([`DynamicStorage`]).  The other
+/// implementations fix the strategy in the type instead, so that the value is
+/// stored like in the [`Fragile`] or [`Sticky`] it would end up in anyways.
+///
+/// This trait is sealed and cannot be implemented outside of this crate.
+pub trait Storage<T: 'static>: private::Sealed + 'static {
+    #[doc(hidden)]
+    type Inner: Inner<T>;
+
+    #[doc(hidden)]
+    fn wrap(value: T) -> Self::Inner;
+}
+
+/// Picks a [`Strategy`] at runtime.
+///
+/// This is the default storage of [`SemiSticky`].  A value stored in a
+/// [`Fragile`] is boxed so that the wrapper only holds a handle no matter
+/// which strategy is picked.
+#[derive(Debug)]
+pub struct DynamicStorage;
+
+/// Always uses [`Strategy::Fragile`] and stores the value inline.
+#[derive(Debug)]
+pub struct FragileStorage;
+
+/// Always uses [`Strategy::Sticky`].
+#[derive(Debug)]
+pub struct StickyStorage;
+
+/// Always uses [`Strategy::Deferred`] and stores the value inline.
+#[derive(Debug)]
+pub struct DeferredStorage;
+
+impl private::Sealed for DynamicStorage {}
+impl private::Sealed for FragileStorage {}
+impl private::Sealed for StickyStorage {}
+impl private::Sealed for DeferredStorage {}
+
+impl<T: 'static> Storage<T> for DynamicStorage {
+    type Inner = SemiStickyImpl<T>;
+
+    #[track_caller]
+    fn wrap(value: T) -> SemiStickyImpl<T> {
+        SemiStickyImpl::new(value, Strategy::Auto)
+    }
+}
+
+impl<T: 'static> Storage<T> for FragileStorage {
+    type Inner = Fragile<T>;
+
+    fn wrap(value: T) -> Fragile<T> {
+        Fragile::new(value)
+    }
+}
+
+impl<T: 'static> Storage<T> for StickyStorage {
+    type Inner = Sticky<T>;
+
+    #[track_caller]
+    fn wrap(value: T) -> Sticky<T> {
+        Sticky::new(value)
+    }
+}
+
+impl<T: 'static> Storage<T> for DeferredStorage {
+    type Inner = Fragile<T>;
+
+    fn wrap(value: T) -> Fragile<T> {
+        Fragile::new_deferred(value)
+    }
+}
+
+/// The operations a [`SemiSticky`] forwards to the wrapper of its storage.
+#[doc(hidden)]
+pub trait Inner<T: 'static>: Sized + Send + Sync {
+    fn strategy(&self) -> Strategy;
+    fn is_valid(&self) -> bool;
+    fn home(&self) -> &Home;
+    fn set_violation_policy(&mut self, policy: ViolationPolicy);
+    fn violation(&self, kind: ViolationKind);
+    fn into_inner(self) -> T;
+    fn try_into_inner(self) -> Result<T, IntoInnerError<Self>>;
+    fn into_fragile(self) -> Result<Fragile<T>, IntoInnerError<Self>>;
+    fn into_sticky(self) -> Result<Sticky<T>, IntoInnerError<Self>>;
+    fn get<'stack>(&'stack self, _proof: &'stack StackToken) -> &'stack T;
+    fn get_mut<'stack>(&'stack mut self, _proof: &'stack StackToken) -> &'stack mut T;
+    fn try_get<'stack>(
+        &'stack self,
+        _proof: &'stack StackToken,
+    ) -> Result<&'stack T, InvalidThreadAccess>;
+    fn try_get_mut<'stack>(
+        &'stack mut self,
+        _proof: &'stack StackToken,
+    ) -> Result<&'stack mut T, InvalidThreadAccess>;
+    fn with_mut<F: FnOnce(&mut T) -> R, R>(&mut self, f: F) -> R;
+}
+
+impl<T: 'static> Inner<T> for Fragile<T> {
+    fn strategy(&self) -> Strategy {
+        if self.is_deferred() {
+            Strategy::Deferred
+        } else {
+            Strategy::Fragile
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        Fragile::is_valid(self)
+    }
+
+    fn home(&self) -> &Home {
+        Fragile::home(self)
+    }
+
+    fn set_violation_policy(&mut self, policy: ViolationPolicy) {
+        Fragile::set_violation_policy(self, policy)
+    }
+
+    #[track_caller]
+    fn violation(&self, kind: ViolationKind) {
+        Fragile::violation(self, kind)
+    }
+
+    #[track_caller]
+    fn into_inner(self) -> T {
+        Fragile::into_inner(self)
+    }
+
+    #[track_caller]
+    fn try_into_inner(self) -> Result<T, IntoInnerError<Self>> {
+        Fragile::try_into_inner(self)
+    }
+
+    #[track_caller]
+    fn into_fragile(self) -> Result<Fragile<T>, IntoInnerError<Self>> {
+        if self.is_valid() {
+            Ok(self)
+        } else {
+            let err = InvalidThreadAccess::new::<T>(WrapperKind::Fragile, self.home());
+            Err(IntoInnerError::new(self, err))
+        }
+    }
+
+    #[track_caller]
+    fn into_sticky(self) -> Result<Sticky<T>, IntoInnerError<Self>> {
+        Fragile::into_sticky(self)
+    }
+
+    #[track_caller]
+    fn get<'stack>(&'stack self, _proof: &'stack StackToken) -> &'stack T {
+        Fragile::get(self)
+    }
+
+    #[track_caller]
+    fn get_mut<'stack>(&'stack mut self, _proof: &'stack StackToken) -> &'stack mut T {
+        Fragile::get_mut(self)
+    }
+
+    #[track_caller]
+    fn try_get<'stack>(
+        &'stack self,
+        _proof: &'stack StackToken,
+    ) -> Result<&'stack T, InvalidThreadAccess> {
+        Fragile::try_get(self)
+    }
+
+    #[track_caller]
+    fn try_get_mut<'stack>(
+        &'stack mut self,
+        _proof: &'stack StackToken,
+    ) -> Result<&'stack mut T, InvalidThreadAccess> {
+        Fragile::try_get_mut(self)
+    }
+
+    #[track_caller]
+    fn with_mut<F: FnOnce(&mut T) -> R, R>(&mut self, f: F) -> R {
+        f(Fragile::get_mut(self))
+    }
+}
+
+impl<T: 'static> Inner<T> for Sticky<T> {
+    fn strategy(&self) -> Strategy {
+        Strategy::Sticky
+    }
+
+    fn is_valid(&self) -> bool {
+        Sticky::is_valid(self)
+    }
+
+    fn home(&self) -> &Home {
+        Sticky::home(self)
+    }
+
+    fn set_violation_policy(&mut self, policy: ViolationPolicy) {
+        Sticky::set_violation_policy(self, policy)
+    }
+
+    #[track_caller]
+    fn violation(&self, kind: ViolationKind) {
+        Sticky::violation(self, kind)
+    }
+
+    #[track_caller]
+    fn into_inner(self) -> T {
+        Sticky::into_inner(self)
+    }
+
+    #[track_caller]
+    fn try_into_inner(self) -> Result<T, IntoInnerError<Self>> {
+        Sticky::try_into_inner(self)
+    }
+
+    #[track_caller]
+    fn into_fragile(self) -> Result<Fragile<T>, IntoInnerError<Self>> {
+        Sticky::into_fragile(self)
+    }
+
+    #[track_caller]
+    fn into_sticky(self) -> Result<Sticky<T>, IntoInnerError<Self>> {
+        if self.is_valid() {
+            Ok(self)
+        } else {
+            let err = InvalidThreadAccess::new::<T>(WrapperKind::Sticky, self.home());
+            Err(IntoInnerError::new(self, err))
+        }
+    }
+
+    #[track_caller]
+    fn get<'stack>(&'stack self, _proof: &'stack StackToken) -> &'stack T {
+        Sticky::get(self, _proof)
+    }
+
+    #[track_caller]
+    fn get_mut<'stack>(&'stack mut self, _proof: &'stack StackToken) -> &'stack mut T {
+        Sticky::get_mut(self, _proof)
+    }
+
+    #[track_caller]
+    fn try_get<'stack>(
+        &'stack self,
+        _proof: &'stack StackToken,
+    ) -> Result<&'stack T, InvalidThreadAccess> {
+        Sticky::try_get(self, _proof)
+    }
+
+    #[track_caller]
+    fn try_get_mut<'stack>(
+        &'stack mut self,
+        _proof: &'stack StackToken,
+    ) -> Result<&'stack mut T, InvalidThreadAccess> {
+        Sticky::try_get_mut(self, _proof)
+    }
+
+    #[track_caller]
+    fn with_mut<F: FnOnce(&mut T) -> R, R>(&mut self, f: F) -> R {
+        Sticky::with_mut(self, f)
+    }
+}
+
+/// The storage of a [`SemiSticky`] with [`DynamicStorage`].
+#[doc(hidden)]
+pub enum SemiStickyImpl<T: 'static> {
     Fragile(Box<Fragile<T>>),
     Sticky(Sticky<T>),
 }
 
+impl<T: 'static> SemiStickyImpl<T> {
+    #[track_caller]
+    fn new(value: T, strategy: Strategy) -> SemiStickyImpl<T> {
+        match strategy {
+            Strategy::Auto if mem::needs_drop::<T>() => SemiStickyImpl::Sticky(Sticky::new(value)),
+            Strategy::Auto | Strategy::Fragile => {
+                SemiStickyImpl::Fragile(Box::new(Fragile::new(value)))
+            }
+            Strategy::Sticky => SemiStickyImpl::Sticky(Sticky::new(value)),
+            Strategy::Deferred => SemiStickyImpl::Fragile(Box::new(Fragile::new_deferred(value))),
+        }
+    }
+}
+
+impl<T: 'static> Inner<T> for SemiStickyImpl<T> {
+    fn strategy(&self) -> Strategy {
+        match *self {
+            SemiStickyImpl::Fragile(ref inner) => inner.strategy(),
+            SemiStickyImpl::Sticky(ref inner) => inner.strategy(),
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        match *self {
+            SemiStickyImpl::Fragile(ref inner) => inner.is_valid(),
+            SemiStickyImpl::Sticky(ref inner) => inner.is_valid(),
+        }
+    }
+
+    fn home(&self) -> &Home {
+        match *self {
+            SemiStickyImpl::Fragile(ref inner) => inner.home(),
+            SemiStickyImpl::Sticky(ref inner) => inner.home(),
+        }
+    }
+
+    fn set_violation_policy(&mut self, policy: ViolationPolicy) {
+        match *self {
+            SemiStickyImpl::Fragile(ref mut inner) => inner.set_violation_policy(policy),
+            SemiStickyImpl::Sticky(ref mut inner) => inner.set_violation_policy(policy),
+        }
+    }
+
+    #[track_caller]
+    fn violation(&self, kind: ViolationKind) {
+        match *self {
+            SemiStickyImpl::Fragile(ref inner) => inner.violation(kind),
+            SemiStickyImpl::Sticky(ref inner) => inner.violation(kind),
+        }
+    }
+
+    #[track_caller]
+    fn into_inner(self) -> T {
+        match self {
+            SemiStickyImpl::Fragile(inner) => inner.into_inner(),
+            SemiStickyImpl::Sticky(inner) => inner.into_inner(),
+        }
+    }
+
+    #[track_caller]
+    fn try_into_inner(self) -> Result<T, IntoInnerError<Self>> {
+        match self {
+            SemiStickyImpl::Fragile(inner) => inner
+                .try_into_inner()
+                .map_err(|err| err.map(|inner| SemiStickyImpl::Fragile(Box::new(inner)))),
+            SemiStickyImpl::Sticky(inner) => inner
+                .try_into_inner()
+                .map_err(|err| err.map(SemiStickyImpl::Sticky)),
+        }
+    }
+
+    #[track_caller]
+    fn into_fragile(self) -> Result<Fragile<T>, IntoInnerError<Self>> {
+        match self {
+            SemiStickyImpl::Fragile(inner) => (*inner)
+                .into_fragile()
+                .map_err(|err| err.map(|inner| SemiStickyImpl::Fragile(Box::new(inner)))),
+            SemiStickyImpl::Sticky(inner) => {
+                Sticky::into_fragile(inner).map_err(|err| err.map(SemiStickyImpl::Sticky))
+            }
+        }
+    }
+
+    #[track_caller]
+    fn into_sticky(self) -> Result<Sticky<T>, IntoInnerError<Self>> {
+        match self {
+            SemiStickyImpl::Fragile(inner) => inner
+                .into_sticky()
+                .map_err(|err| err.map(|inner| SemiStickyImpl::Fragile(Box::new(inner)))),
+            SemiStickyImpl::Sticky(inner) => {
+                Inner::into_sticky(inner).map_err(|err| err.map(SemiStickyImpl::Sticky))
+            }
+        }
+    }
+
+    #[track_caller]
+    fn get<'stack>(&'stack self, _proof: &'stack StackToken) -> &'stack T {
+        match *self {
+            SemiStickyImpl::Fragile(ref inner) => inner.get(),
+            SemiStickyImpl::Sticky(ref inner) => inner.get(_proof),
+        }
+    }
+
+    #[track_caller]
+    fn get_mut<'stack>(&'stack mut self, _proof: &'stack StackToken) -> &'stack mut T {
+        match *self {
+            SemiStickyImpl::Fragile(ref mut inner) => inner.get_mut(),
+            SemiStickyImpl::Sticky(ref mut inner) => inner.get_mut(_proof),
+        }
+    }
+
+    #[track_caller]
+    fn try_get<'stack>(
+        &'stack self,
+        _proof: &'stack StackToken,
+    ) -> Result<&'stack T, InvalidThreadAccess> {
+        match *self {
+            SemiStickyImpl::Fragile(ref inner) => inner.try_get(),
+            SemiStickyImpl::Sticky(ref inner) => inner.try_get(_proof),
+        }
+    }
+
+    #[track_caller]
+    fn try_get_mut<'stack>(
+        &'stack mut self,
+        _proof: &'stack StackToken,
+    ) -> Result<&'stack mut T, InvalidThreadAccess> {
+        match *self {
+            SemiStickyImpl::Fragile(ref mut inner) => inner.try_get_mut(),
+            SemiStickyImpl::Sticky(ref mut inner) => inner.try_get_mut(_proof),
+        }
+    }
+
+    #[track_caller]
+    fn with_mut<F: FnOnce(&mut T) -> R, R>(&mut self, f: F) -> R {
+        match *self {
+            SemiStickyImpl::Fragile(ref mut inner) => f(inner.get_mut()),
+            SemiStickyImpl::Sticky(ref mut inner) => inner.with_mut(f),
+        }
+    }
+}
+
+impl<T: Clone> Clone for SemiStickyImpl<T> {
+    #[track_caller]
+    fn clone(&self) -> SemiStickyImpl<T> {
+        match *self {
+            SemiStickyImpl::Fragile(ref inner) => SemiStickyImpl::Fragile(inner.clone()),
+            SemiStickyImpl::Sticky(ref inner) => SemiStickyImpl::Sticky(inner.clone()),
+        }
+    }
+}
+
 /// A [`SemiSticky<T>`] keeps a value T stored in a thread if it has a drop.
 ///
 /// This is a combined version of [`Fragile`] and [`Sticky`].  If the type
 /// does not have a drop it will effectively be a [`Fragile`], otherwise it
-/// will be internally behave like a [`Sticky`].
+/// will be internally behave like a [`Sticky`].  A different [`Strategy`]
+/// can be picked with [`with_strategy`](Self::with_strategy).
+///
+/// The strategy is chosen at runtime, so every access dispatches between the
+/// two, and a value stored in a [`Fragile`] is boxed to keep the wrapper as
+/// small as a [`Sticky`].  If the choice is known up front, the second type
+/// parameter selects a [`Storage`] that fixes the strategy in the type:
+///
+/// ```
+/// use fragile::{SemiSticky, StickyStorage, Strategy};
+///
+/// let val = SemiSticky::<_, StickyStorage>::with_storage([0u8; 1024]);
+/// assert_eq!(val.strategy(), Strategy::Sticky);
+/// ```
 ///
 /// This type requires `T: 'static` for the same reasons as [`Sticky`] and
 /// also uses [`StackToken`]s.
-pub struct SemiSticky<T: 'static> {
-    inner: SemiStickyImpl<T>,
+pub struct SemiSticky<T: 'static, S: Storage<T> = DynamicStorage> {
+    inner: S::Inner,
 }
 
 impl<T> SemiSticky<T> {
@@ -33,24 +498,99 @@ impl<T> SemiSticky<T> {
     /// only the original thread can interact with the value.  In case the
     /// value does not have `Drop` it will be stored in the [`Fragile`]
     /// instead.
+    #[track_caller]
     pub fn new(value: T) -> Self {
+        SemiSticky::with_strategy(value, Strategy::Auto)
+    }
+
+    /// Creates a new [`SemiSticky`] wrapping a `value` stored with the given
+    /// [`Strategy`].
+    ///
+    /// ```
+    /// use fragile::{SemiSticky, Strategy};
+    ///
+    /// let val = SemiSticky::with_strategy(vec![1, 2, 3], Strategy::Deferred);
+    /// assert_eq!(val.strategy(), Strategy::Deferred);
+    /// ```
+    #[track_caller]
+    pub fn with_strategy(value: T, strategy: Strategy) -> Self {
         SemiSticky {
-            inner: if mem::needs_drop::<T>() {
-                SemiStickyImpl::Sticky(Sticky::new(value))
-            } else {
-                SemiStickyImpl::Fragile(Box::new(Fragile::new(value)))
-            },
+            inner: SemiStickyImpl::new(value, strategy),
         }
     }
+}
+
+impl<T, S: Storage<T>> SemiSticky<T, S> {
+    /// Creates a new [`SemiSticky`] wrapping a `value` stored as selected by
+    /// the [`Storage`] `S`.
+    ///
+    /// With [`DynamicStorage`] this is the same as [`new`](SemiSticky::new).
+    #[track_caller]
+    pub fn with_storage(value: T) -> Self {
+        SemiSticky {
+            inner: S::wrap(value),
+        }
+    }
+
+    /// Returns the [`Strategy`] the value is stored with.
+    ///
+    /// [`Strategy::Auto`] is resolved when the value is wrapped, so this
+    /// never returns it.
+    pub fn strategy(&self) -> Strategy {
+        self.inner.strategy()
+    }
 
     /// Returns `true` if the access is valid.
     ///
     /// This will be `false` if the value was sent to another thread.
     pub fn is_valid(&self) -> bool {
-        match self.inner {
-            SemiStickyImpl::Fragile(ref inner) => inner.is_valid(),
-            SemiStickyImpl::Sticky(ref inner) => inner.is_valid(),
-        }
+        self.inner.is_valid()
+    }
+
+    /// Returns the [`Home`] of the thread that wrapped the value.
+    pub fn home(&self) -> &Home {
+        self.inner.home()
+    }
+
+    /// Returns `true` if the thread that wrapped the value is still running.
+    pub fn owner_alive(&self) -> bool {
+        self.home().is_alive()
+    }
+
+    /// Returns `true` if the thread that wrapped the value has exited.
+    ///
+    /// An orphaned value can never be accessed again.
+    pub fn is_orphaned(&self) -> bool {
+        !self.owner_alive()
+    }
+
+    /// Sets the [`ViolationPolicy`] of this value.
+    ///
+    /// This overrides the global policy for this value.
+    pub fn set_violation_policy(&mut self, policy: ViolationPolicy) {
+        self.inner.set_violation_policy(policy)
+    }
+
+    #[cfg(feature = "future")]
+    #[cold]
+    #[track_caller]
+    pub(crate) fn violation(&self, kind: ViolationKind) {
+        self.inner.violation(kind)
+    }
+
+    /// Runs `f` with the wrapped value on the thread that wrapped it.
+    ///
+    /// This works like [`Sticky::run_on_home`].
+    pub fn run_on_home<F, R>(self, f: F) -> HomeTask<(SemiSticky<T, S>, R)>
+    where
+        F: FnOnce(&mut T) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let home = self.home().clone();
+        home::run_on(&home, self, move |this| {
+            crate::stack_token!(tok);
+            f(this.get_mut(tok))
+        })
     }
 
     /// Consumes the [`SemiSticky`], returning the wrapped value.
@@ -61,26 +601,37 @@ impl<T> SemiSticky<T> {
     /// original value was created.
     #[track_caller]
     pub fn into_inner(self) -> T {
-        match self.inner {
-            SemiStickyImpl::Fragile(inner) => inner.into_inner(),
-            SemiStickyImpl::Sticky(inner) => inner.into_inner(),
-        }
+        self.inner.into_inner()
     }
 
     /// Consumes the [`SemiSticky`], returning the wrapped value if successful.
     ///
     /// The wrapped value is returned if this is called from the same thread
     /// as the one where the original value was created, otherwise the
-    /// [`SemiSticky`] is returned as `Err(self)`.
-    pub fn try_into_inner(self) -> Result<T, Self> {
-        match self.inner {
-            SemiStickyImpl::Fragile(inner) => inner.try_into_inner().map_err(|inner| SemiSticky {
-                inner: SemiStickyImpl::Fragile(Box::new(inner)),
-            }),
-            SemiStickyImpl::Sticky(inner) => inner.try_into_inner().map_err(|inner| SemiSticky {
-                inner: SemiStickyImpl::Sticky(inner),
-            }),
-        }
+    /// [`SemiSticky`] is returned as part of the [`IntoInnerError`].
+    #[track_caller]
+    pub fn try_into_inner(self) -> Result<T, IntoInnerError<Self>> {
+        self.inner.try_into_inner().map_err(wrap_error)
+    }
+
+    /// Converts the [`SemiSticky`] into a [`Fragile`] without cloning the value.
+    ///
+    /// A value stored in a [`Sticky`] is converted with [`Sticky::into_fragile`].
+    /// Fails if called from a different thread than the one where the original
+    /// value was created.
+    #[track_caller]
+    pub fn into_fragile(self) -> Result<Fragile<T>, IntoInnerError<Self>> {
+        self.inner.into_fragile().map_err(wrap_error)
+    }
+
+    /// Converts the [`SemiSticky`] into a [`Sticky`] without cloning the value.
+    ///
+    /// A value stored in a [`Fragile`] is converted with [`Fragile::into_sticky`].
+    /// Fails if called from a different thread than the one where the original
+    /// value was created.
+    #[track_caller]
+    pub fn into_sticky(self) -> Result<Sticky<T>, IntoInnerError<Self>> {
+        self.inner.into_sticky().map_err(wrap_error)
     }
 
     /// Immutably borrows the wrapped value.
@@ -91,10 +642,7 @@ impl<T> SemiSticky<T> {
     /// For a non-panicking variant, use [`try_get`](Self::try_get).
     #[track_caller]
     pub fn get<'stack>(&'stack self, _proof: &'stack StackToken) -> &'stack T {
-        match self.inner {
-            SemiStickyImpl::Fragile(ref inner) => inner.get(),
-            SemiStickyImpl::Sticky(ref inner) => inner.get(_proof),
-        }
+        self.inner.get(_proof)
     }
 
     /// Mutably borrows the wrapped value.
@@ -105,120 +653,181 @@ impl<T> SemiSticky<T> {
     /// For a non-panicking variant, use [`try_get_mut`](Self::try_get_mut).
     #[track_caller]
     pub fn get_mut<'stack>(&'stack mut self, _proof: &'stack StackToken) -> &'stack mut T {
-        match self.inner {
-            SemiStickyImpl::Fragile(ref mut inner) => inner.get_mut(),
-            SemiStickyImpl::Sticky(ref mut inner) => inner.get_mut(_proof),
-        }
+        self.inner.get_mut(_proof)
+    }
+
+    /// Runs `f` with exclusive access to the value for the forwarding impls.
+    ///
+    /// See [`Sticky::with_mut`].
+    #[track_caller]
+    pub(crate) fn with_mut<F: FnOnce(&mut T) -> R, R>(&mut self, f: F) -> R {
+        self.inner.with_mut(f)
     }
 
     /// Tries to immutably borrow the wrapped value.
     ///
-    /// Returns `None` if the calling thread is not the one that wrapped the value.
+    /// Returns an error if the calling thread is not the one that wrapped the value.
+    #[track_caller]
     pub fn try_get<'stack>(
         &'stack self,
         _proof: &'stack StackToken,
     ) -> Result<&'stack T, InvalidThreadAccess> {
-        match self.inner {
-            SemiStickyImpl::Fragile(ref inner) => inner.try_get(),
-            SemiStickyImpl::Sticky(ref inner) => inner.try_get(_proof),
-        }
+        self.inner
+            .try_get(_proof)
+            .map_err(|err| err.set_wrapper(WrapperKind::SemiSticky))
     }
 
     /// Tries to mutably borrow the wrapped value.
     ///
-    /// Returns `None` if the calling thread is not the one that wrapped the value.
+    /// Returns an error if the calling thread is not the one that wrapped the value.
+    #[track_caller]
     pub fn try_get_mut<'stack>(
         &'stack mut self,
         _proof: &'stack StackToken,
     ) -> Result<&'stack mut T, InvalidThreadAccess> {
-        match self.inner {
-            SemiStickyImpl::Fragile(ref mut inner) => inner.try_get_mut(),
-            SemiStickyImpl::Sticky(ref mut inner) => inner.try_get_mut(_proof),
+        self.inner
+            .try_get_mut(_proof)
+            .map_err(|err| err.set_wrapper(WrapperKind::SemiSticky))
+    }
+}
+
+/// Turns the error of the storage into one of the [`SemiSticky`].
+fn wrap_error<T, S: Storage<T>>(err: IntoInnerError<S::Inner>) -> IntoInnerError<SemiSticky<T, S>> {
+    let (inner, err) = err.into_parts();
+    IntoInnerError::new(
+        SemiSticky { inner },
+        err.set_wrapper(WrapperKind::SemiSticky),
+    )
+}
+
+impl<T, S: Storage<T>> From<T> for SemiSticky<T, S> {
+    #[inline]
+    #[track_caller]
+    fn from(t: T) -> SemiSticky<T, S> {
+        SemiSticky::with_storage(t)
+    }
+}
+
+impl<T> From<Fragile<T>> for SemiSticky<T> {
+    /// Wraps the [`Fragile`] as is.  This works on any thread.
+    #[inline]
+    fn from(value: Fragile<T>) -> SemiSticky<T> {
+        SemiSticky {
+            inner: SemiStickyImpl::Fragile(Box::new(value)),
         }
     }
 }
 
-impl<T> From<T> for SemiSticky<T> {
+impl<T> From<Sticky<T>> for SemiSticky<T> {
+    /// Wraps the [`Sticky`] as is.  This works on any thread.
     #[inline]
-    fn from(t: T) -> SemiSticky<T> {
-        SemiSticky::new(t)
+    fn from(value: Sticky<T>) -> SemiSticky<T> {
+        SemiSticky {
+            inner: SemiStickyImpl::Sticky(value),
+        }
     }
 }
 
-impl<T: Clone> Clone for SemiSticky<T> {
+impl<T, S: Storage<T>> TryFrom<SemiSticky<T, S>> for Fragile<T> {
+    type Error = IntoInnerError<SemiSticky<T, S>>;
+
+    /// Converts with [`SemiSticky::into_fragile`].
+    #[track_caller]
+    fn try_from(value: SemiSticky<T, S>) -> Result<Fragile<T>, IntoInnerError<SemiSticky<T, S>>> {
+        value.into_fragile()
+    }
+}
+
+impl<T, S: Storage<T>> TryFrom<SemiSticky<T, S>> for Sticky<T> {
+    type Error = IntoInnerError<SemiSticky<T, S>>;
+
+    /// Converts with [`SemiSticky::into_sticky`].
+    #[track_caller]
+    fn try_from(value: SemiSticky<T, S>) -> Result<Sticky<T>, IntoInnerError<SemiSticky<T, S>>> {
+        value.into_sticky()
+    }
+}
+
+impl<T: Clone, S: Storage<T>> Clone for SemiSticky<T, S>
+where
+    S::Inner: Clone,
+{
     #[inline]
     #[track_caller]
-    fn clone(&self) -> SemiSticky<T> {
-        crate::stack_token!(tok);
-        SemiSticky::new(self.get(tok).clone())
+    fn clone(&self) -> SemiSticky<T, S> {
+        // cloning the inner wrapper keeps the strategy and the policy.
+        SemiSticky {
+            inner: self.inner.clone(),
+        }
     }
 }
 
-impl<T: Default> Default for SemiSticky<T> {
+impl<T: Default, S: Storage<T>> Default for SemiSticky<T, S> {
     #[inline]
-    fn default() -> SemiSticky<T> {
-        SemiSticky::new(T::default())
+    #[track_caller]
+    fn default() -> SemiSticky<T, S> {
+        SemiSticky::with_storage(T::default())
     }
 }
 
-impl<T: PartialEq> PartialEq for SemiSticky<T> {
+impl<T: PartialEq, S: Storage<T>> PartialEq for SemiSticky<T, S> {
     #[inline]
     #[track_caller]
-    fn eq(&self, other: &SemiSticky<T>) -> bool {
+    fn eq(&self, other: &SemiSticky<T, S>) -> bool {
         crate::stack_token!(tok);
         *self.get(tok) == *other.get(tok)
     }
 }
 
-impl<T: Eq> Eq for SemiSticky<T> {}
+impl<T: Eq, S: Storage<T>> Eq for SemiSticky<T, S> {}
 
-impl<T: PartialOrd> PartialOrd for SemiSticky<T> {
+impl<T: PartialOrd, S: Storage<T>> PartialOrd for SemiSticky<T, S> {
     #[inline]
     #[track_caller]
-    fn partial_cmp(&self, other: &SemiSticky<T>) -> Option<cmp::Ordering> {
+    fn partial_cmp(&self, other: &SemiSticky<T, S>) -> Option<cmp::Ordering> {
         crate::stack_token!(tok);
         self.get(tok).partial_cmp(other.get(tok))
     }
 
     #[inline]
     #[track_caller]
-    fn lt(&self, other: &SemiSticky<T>) -> bool {
+    fn lt(&self, other: &SemiSticky<T, S>) -> bool {
         crate::stack_token!(tok);
         *self.get(tok) < *other.get(tok)
     }
 
     #[inline]
     #[track_caller]
-    fn le(&self, other: &SemiSticky<T>) -> bool {
+    fn le(&self, other: &SemiSticky<T, S>) -> bool {
         crate::stack_token!(tok);
         *self.get(tok) <= *other.get(tok)
     }
 
     #[inline]
     #[track_caller]
-    fn gt(&self, other: &SemiSticky<T>) -> bool {
+    fn gt(&self, other: &SemiSticky<T, S>) -> bool {
         crate::stack_token!(tok);
         *self.get(tok) > *other.get(tok)
     }
 
     #[inline]
     #[track_caller]
-    fn ge(&self, other: &SemiSticky<T>) -> bool {
+    fn ge(&self, other: &SemiSticky<T, S>) -> bool {
         crate::stack_token!(tok);
         *self.get(tok) >= *other.get(tok)
     }
 }
 
-impl<T: Ord> Ord for SemiSticky<T> {
+impl<T: Ord, S: Storage<T>> Ord for SemiSticky<T, S> {
     #[inline]
     #[track_caller]
-    fn cmp(&self, other: &SemiSticky<T>) -> cmp::Ordering {
+    fn cmp(&self, other: &SemiSticky<T, S>) -> cmp::Ordering {
         crate::stack_token!(tok);
         self.get(tok).cmp(other.get(tok))
     }
 }
 
-impl<T: fmt::Display> fmt::Display for SemiSticky<T> {
+impl<T: fmt::Display, S: Storage<T>> fmt::Display for SemiSticky<T, S> {
     #[track_caller]
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         crate::stack_token!(tok);
@@ -226,7 +835,7 @@ impl<T: fmt::Display> fmt::Display for SemiSticky<T> {
     }
 }
 
-impl<T: fmt::Debug> fmt::Debug for SemiSticky<T> {
+impl<T: fmt::Debug, S: Storage<T>> fmt::Debug for SemiSticky<T, S> {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         crate::stack_token!(tok);
         match self.try_get(tok) {
@@ -247,6 +856,140 @@ impl<T: fmt::Debug> fmt::Debug for SemiSticky<T> {
     }
 }
 
+impl<T: Hash, S: Storage<T>> Hash for SemiSticky<T, S> {
+    #[inline]
+    #[track_caller]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        crate::stack_token!(tok);
+        self.get(tok).hash(state)
+    }
+}
+
+impl<T: Iterator, S: Storage<T>> Iterator for SemiSticky<T, S> {
+    type Item = T::Item;
+
+    #[inline]
+    #[track_caller]
+    fn next(&mut self) -> Option<T::Item> {
+        self.with_mut(|x| x.next())
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        crate::stack_token!(tok);
+        match self.try_get(tok) {
+            Ok(x) => x.size_hint(),
+            Err(_) => (0, None),
+        }
+    }
+}
+
+impl<T: DoubleEndedIterator, S: Storage<T>> DoubleEndedIterator for SemiSticky<T, S> {
+    #[inline]
+    #[track_caller]
+    fn next_back(&mut self) -> Option<T::Item> {
+        self.with_mut(|x| x.next_back())
+    }
+}
+
+impl<T: ExactSizeIterator, S: Storage<T>> ExactSizeIterator for SemiSticky<T, S> {
+    #[inline]
+    #[track_caller]
+    fn len(&self) -> usize {
+        crate::stack_token!(tok);
+        self.get(tok).len()
+    }
+}
+
+impl<T: io::Read, S: Storage<T>> io::Read for SemiSticky<T, S> {
+    #[inline]
+    #[track_caller]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.with_mut(|x| x.read(buf))
+    }
+
+    #[inline]
+    #[track_caller]
+    fn read_vectored(&mut self, bufs: &mut [io::IoSliceMut<'_>]) -> io::Result<usize> {
+        self.with_mut(|x| x.read_vectored(bufs))
+    }
+}
+
+impl<T: io::Write, S: Storage<T>> io::Write for SemiSticky<T, S> {
+    #[inline]
+    #[track_caller]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.with_mut(|x| x.write(buf))
+    }
+
+    #[inline]
+    #[track_caller]
+    fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+        self.with_mut(|x| x.write_vectored(bufs))
+    }
+
+    #[inline]
+    #[track_caller]
+    fn flush(&mut self) -> io::Result<()> {
+        self.with_mut(|x| x.flush())
+    }
+}
+
+impl<T: io::Seek, S: Storage<T>> io::Seek for SemiSticky<T, S> {
+    #[inline]
+    #[track_caller]
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.with_mut(|x| x.seek(pos))
+    }
+}
+
+// the source cannot be forwarded as the borrow could outlive the registry of
+// the owning thread.
+impl<T: error::Error, S: Storage<T>> error::Error for SemiSticky<T, S> {}
+
+impl<T: error::Error, S: Storage<T>> SemiSticky<T, S> {
+    /// Returns the source of the wrapped error.
+    ///
+    /// The `Error` implementation of a [`SemiSticky`] cannot forward
+    /// [`source`](error::Error::source) as the borrow could outlive the
+    /// registry of the owning thread, so it always returns `None`.  This
+    /// borrows the source with a [`StackToken`] instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from a different thread than the one where the
+    /// original value was created.
+    #[track_caller]
+    pub fn source_with<'stack>(
+        &'stack self,
+        _proof: &'stack StackToken,
+    ) -> Option<&'stack (dyn error::Error + 'static)> {
+        self.get(_proof).source()
+    }
+}
+
+impl<T: io::BufRead, S: Storage<T>> SemiSticky<T, S> {
+    /// Returns the buffer of the wrapped reader.
+    ///
+    /// A [`SemiSticky`] cannot implement [`BufRead`](io::BufRead) for the same
+    /// reason it cannot forward the source of an error.  This borrows the
+    /// buffer with a [`StackToken`] instead.  Consume from it through
+    /// [`get_mut`](Self::get_mut).
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from a different thread than the one where the
+    /// original value was created or if [`StickyWeak`](crate::StickyWeak)
+    /// handles to the value exist as the buffer borrows it.
+    #[track_caller]
+    pub fn fill_buf_with<'stack>(
+        &'stack mut self,
+        _proof: &'stack StackToken,
+    ) -> io::Result<&'stack [u8]> {
+        self.get_mut(_proof).fill_buf()
+    }
+}
+
 #[test]
 fn test_basic() {
     use std::thread;
@@ -349,3 +1092,176 @@ fn test_rc_sending() {
     .join()
     .unwrap();
 }
+
+#[test]
+fn test_access_error() {
+    use std::thread;
+    let val = SemiSticky::new(true);
+    thread::spawn(move || {
+        crate::stack_token!(tok);
+        let err = val.try_get(tok).unwrap_err();
+        assert_eq!(err.wrapper(), WrapperKind::SemiSticky);
+        let err = val.try_into_inner().unwrap_err();
+        assert_eq!(err.error().wrapper(), WrapperKind::SemiSticky);
+    })
+    .join()
+    .unwrap();
+}
+
+#[test]
+fn test_orphaned() {
+    use std::thread;
+    let val = SemiSticky::new(true);
+    assert!(val.owner_alive());
+    let val = thread::spawn(|| SemiSticky::new(vec![1, 2, 3]))
+        .join()
+        .unwrap();
+    assert!(val.is_orphaned());
+}
+
+#[test]
+fn test_std_traits() {
+    use std::io::Write;
+    use std::thread;
+
+    let mut iter = SemiSticky::new((0..4).rev());
+    assert_eq!(iter.len(), 4);
+    assert_eq!(iter.next(), Some(3));
+    assert_eq!(iter.next_back(), Some(0));
+
+    let mut out = SemiSticky::new(Vec::new());
+    write!(out, "{}", 42).unwrap();
+    crate::stack_token!(tok);
+    assert_eq!(out.get(tok), b"42");
+
+    let iter = SemiSticky::new(0..4);
+    thread::spawn(move || assert_eq!(iter.size_hint(), (0, None)))
+        .join()
+        .unwrap();
+
+    #[derive(Debug)]
+    struct Outer(std::fmt::Error);
+    impl std::fmt::Display for Outer {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "outer")
+        }
+    }
+    impl error::Error for Outer {
+        fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+    let err = SemiSticky::new(Outer(std::fmt::Error));
+    assert!(err.source_with(tok).unwrap().is::<std::fmt::Error>());
+
+    let mut reader = SemiSticky::new(&b"hello"[..]);
+    assert_eq!(reader.fill_buf_with(tok).unwrap(), b"hello");
+}
+
+#[test]
+fn test_conversions() {
+    use crate::{registry, AccessErrorReason};
+    use std::rc::Rc;
+    use std::thread;
+
+    let before = registry::stats().entries();
+    let value = Rc::new(42);
+    let val = Fragile::new(value.clone()).into_sticky().unwrap();
+    assert_eq!(registry::stats().entries(), before + 1);
+    let val = Fragile::<Rc<i32>>::try_from(val).unwrap();
+    assert_eq!(registry::stats().entries(), before);
+    // the fragile defers its destructor like the sticky did.
+    thread::spawn(move || drop(val)).join().unwrap();
+    assert_eq!(Rc::strong_count(&value), 2);
+    crate::pump();
+    assert_eq!(Rc::strong_count(&value), 1);
+
+    let val = Sticky::new(Rc::new(1));
+    let weak = val.downgrade();
+    let val = val.into_fragile().unwrap_err().into_inner();
+    drop(weak);
+    let val: SemiSticky<Rc<i32>> = val.into();
+    let val = thread::spawn(move || {
+        let err = val.into_fragile().unwrap_err();
+        assert_eq!(err.error().reason(), AccessErrorReason::WrongThread);
+        err.into_inner()
+    })
+    .join()
+    .unwrap();
+    let val = Fragile::<Rc<i32>>::try_from(val).unwrap();
+    let val = Sticky::<Rc<i32>>::try_from(val).unwrap();
+    let val: SemiSticky<Rc<i32>> = val.into();
+    crate::stack_token!(tok);
+    assert_eq!(**val.into_sticky().unwrap().get(tok), 1);
+}
+
+#[test]
+fn test_strategy() {
+    use crate::registry;
+    use std::rc::Rc;
+    use std::thread;
+
+    assert_eq!(SemiSticky::new(42).strategy(), Strategy::Fragile);
+    assert_eq!(SemiSticky::new(vec![42]).strategy(), Strategy::Sticky);
+
+    let before = registry::stats().entries();
+    let val = SemiSticky::with_strategy([0u8; 1024], Strategy::Sticky);
+    assert_eq!(val.strategy(), Strategy::Sticky);
+    assert_eq!(registry::stats().entries(), before + 1);
+    drop(val);
+
+    // the wrapper only holds a handle, no matter the strategy.
+    assert!(
+        mem::size_of::<SemiSticky<[u8; 1024]>>()
+            <= mem::size_of::<Sticky<[u8; 1024]>>() + mem::size_of::<usize>()
+    );
+
+    let value = Rc::new(42);
+    let val = SemiSticky::with_strategy(value.clone(), Strategy::Deferred);
+    assert_eq!(val.strategy(), Strategy::Deferred);
+    assert_eq!(registry::stats().entries(), before);
+    // clones keep the strategy.
+    assert_eq!(val.clone().strategy(), Strategy::Deferred);
+    let sticky = SemiSticky::with_strategy(42, Strategy::Sticky);
+    assert_eq!(sticky.clone().strategy(), Strategy::Sticky);
+    drop(sticky);
+    thread::spawn(move || drop(val)).join().unwrap();
+    assert_eq!(Rc::strong_count(&value), 2);
+    crate::pump();
+    assert_eq!(Rc::strong_count(&value), 1);
+}
+
+#[test]
+fn test_static_storage() {
+    use crate::registry;
+    use std::rc::Rc;
+    use std::thread;
+
+    let before = registry::stats().entries();
+    let val = SemiSticky::<_, StickyStorage>::with_storage([0u8; 1024]);
+    assert_eq!(val.strategy(), Strategy::Sticky);
+    assert_eq!(registry::stats().entries(), before + 1);
+    assert_eq!(mem::size_of_val(&val), mem::size_of::<Sticky<[u8; 1024]>>());
+    drop(val);
+
+    // values stored in a fragile are kept inline.
+    let mut val = SemiSticky::<_, FragileStorage>::with_storage(vec![1, 2, 3]);
+    assert_eq!(val.strategy(), Strategy::Fragile);
+    assert_eq!(registry::stats().entries(), before);
+    assert_eq!(mem::size_of_val(&val), mem::size_of::<Fragile<Vec<i32>>>());
+    crate::stack_token!(tok);
+    val.get_mut(tok).push(4);
+    assert_eq!(val.clone().into_inner(), vec![1, 2, 3, 4]);
+
+    let value = Rc::new(42);
+    let val: SemiSticky<_, DeferredStorage> = value.clone().into();
+    assert_eq!(val.strategy(), Strategy::Deferred);
+    let val = thread::spawn(move || {
+        crate::stack_token!(tok);
+        assert!(val.try_get(tok).is_err());
+        val
+    })
+    .join()
+    .unwrap();
+    assert_eq!(**val.into_sticky().unwrap().get(tok), 42);
+}
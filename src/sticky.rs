@@ -1,14 +1,26 @@
 #![allow(clippy::unit_arg)]
 
+use std::any;
 use std::cmp;
+use std::convert::TryFrom;
+use std::error;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io;
 use std::marker::PhantomData;
 use std::mem;
-use std::thread;
-use std::thread::ThreadId;
+use std::num::NonZeroUsize;
+use std::panic::Location;
 
-use crate::errors::InvalidThreadAccess;
+use crate::errors::{AccessErrorReason, IntoInnerError, InvalidThreadAccess, WrapperKind};
+use crate::fragile::Fragile;
+use crate::handoff::{Handoff, ThreadMovable};
+use crate::home::{self, Home, HomeTask};
+use crate::policy::{self, ViolationInfo, ViolationKind, ViolationPolicy};
 use crate::registry;
+use crate::stickyrc::StickyRc;
+use crate::stickyweak::StickyWeak;
+use crate::thread_id;
 use crate::StackToken;
 
 /// A [`Sticky<T>`] keeps a value T stored in a thread.
@@ -26,23 +38,50 @@ use crate::StackToken;
 /// refer to [`stack_token!`](crate::stack_token).
 ///
 /// As this uses TLS internally the general rules about the platform limitations
-/// of destructors for TLS apply.
+/// of destructors for TLS apply.  A [`Sticky`] can be stored in another thread
+/// local, but its value might be destroyed before the destructor of that
+/// thread local runs.  Accessing it afterwards fails with
+/// [`RegistryDestroyed`](AccessErrorReason::RegistryDestroyed) and dropping it
+/// does nothing.  Creating new values at that point requires
+/// [`try_new`](Self::try_new).
 pub struct Sticky<T: 'static> {
     item_id: registry::ItemId,
-    thread_id: ThreadId,
+    thread_id: NonZeroUsize,
+    home: Home,
+    policy: Option<ViolationPolicy>,
     _marker: PhantomData<*mut T>,
 }
 
 impl<T> Drop for Sticky<T> {
     #[track_caller]
     fn drop(&mut self) {
-        // if the type needs dropping we can only do so on the right thread.
-        // worst case we leak the value until the thread dies when drop will be
-        // called by the registry.
-        if mem::needs_drop::<T>() {
-            unsafe {
-                if self.is_valid() {
-                    self.unsafe_take_value();
+        // the value can only be dropped on the right thread.  on a foreign
+        // thread the entry is handed back to the home thread which removes it
+        // the next time it interacts with this crate.  Worst case we leak the
+        // value until the thread dies when drop will be called by the
+        // registry.
+        unsafe {
+            if self.is_valid() {
+                self.home.reclaim();
+                // if the registry is already gone, so is the value.
+                if let Some(entry) = registry::release(self.item_id) {
+                    (entry.drop)(entry.ptr);
+                }
+            } else if self.is_orphaned() {
+                // the registry of the owner already destroyed the value.
+            } else {
+                let item_id = self.item_id;
+                // SAFETY: the registry is only touched on the home thread.
+                self.home.bury(Box::new(move || {
+                    if let Some(entry) = registry::release(item_id) {
+                        registry::record_remote_drop();
+                        (entry.drop)(entry.ptr);
+                    }
+                }));
+                // values without drop glue can be released anywhere, only
+                // their allocation is reclaimed by the home thread.
+                if mem::needs_drop::<T>() {
+                    self.violation(ViolationKind::Drop);
                 }
             }
         }
@@ -56,33 +95,131 @@ impl<T> Sticky<T> {
     /// will be anchored to the thread that created the object.  If the
     /// sticky wrapper type ends up being send from thread to thread
     /// only the original thread can interact with the value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the thread local registry of the current thread was already
+    /// destroyed.  This can only happen if this is called from the destructor
+    /// of a thread local.  For a non-panicking variant, use
+    /// [`try_new`](#method.try_new).
+    #[track_caller]
     pub fn new(value: T) -> Self {
-        let entry = registry::Entry {
-            ptr: Box::into_raw(Box::new(value)).cast(),
-            drop: |ptr| {
-                let ptr = ptr.cast::<T>();
-                // SAFETY: This callback will only be called once, with the
-                // above pointer.
-                drop(unsafe { Box::from_raw(ptr) });
-            },
-        };
+        match Sticky::try_new(value) {
+            Ok(rv) => rv,
+            Err(_) => {
+                panic!("cannot create sticky value after the thread local registry was destroyed.")
+            }
+        }
+    }
 
-        let thread_id = thread::current().id();
-        let item_id = registry::insert(entry);
+    /// Tries to create a new [`Sticky`] wrapping a `value`.
+    ///
+    /// Returns an error with the [`RegistryDestroyed`](AccessErrorReason::RegistryDestroyed)
+    /// reason if the thread local registry of the current thread was already
+    /// destroyed, in which case the value is dropped right away.
+    #[track_caller]
+    pub fn try_new(value: T) -> Result<Self, InvalidThreadAccess> {
+        Sticky::try_insert(value).map_err(|(_, err)| err)
+    }
+
+    /// Like [`try_new`](Self::try_new) but hands the value back on failure.
+    #[track_caller]
+    pub(crate) fn try_insert(value: T) -> Result<Self, (T, InvalidThreadAccess)> {
+        let entry = registry::Entry::new(value, Location::caller());
+
+        let home = Home::current();
+        let thread_id = home.id();
+        home.reclaim();
+        let item_id = match registry::insert(entry) {
+            Ok(item_id) => item_id,
+            Err(entry) => {
+                // SAFETY: the entry never made it into the registry.
+                let value = unsafe { *Box::from_raw(entry.ptr.cast::<T>()) };
+                return Err((
+                    value,
+                    InvalidThreadAccess::with_reason::<T>(
+                        AccessErrorReason::RegistryDestroyed,
+                        WrapperKind::Sticky,
+                        &home,
+                    ),
+                ));
+            }
+        };
 
-        Sticky {
+        Ok(Sticky {
             item_id,
             thread_id,
+            home,
+            policy: None,
             _marker: PhantomData,
+        })
+    }
+
+    #[inline(always)]
+    fn lookup(&self, exclusive: bool, depth: usize) -> Result<*mut T, registry::LookupError> {
+        let ptr = if exclusive {
+            registry::get_exclusive(self.item_id, depth)?
+        } else {
+            registry::get(self.item_id, depth)?
+        };
+        Ok(ptr.cast::<T>())
+    }
+
+    #[inline(always)]
+    #[track_caller]
+    fn with_value<F: FnOnce(*mut T) -> R, R>(&self, exclusive: bool, depth: usize, f: F) -> R {
+        self.assert_thread();
+        self.home.reclaim();
+
+        match self.lookup(exclusive, depth) {
+            Ok(ptr) => f(ptr),
+            Err(err) => lookup_failed(err),
+        }
+    }
+
+    #[inline(always)]
+    #[track_caller]
+    fn try_with_value<F: FnOnce(*mut T) -> R, R>(
+        &self,
+        exclusive: bool,
+        depth: usize,
+        f: F,
+    ) -> Result<R, InvalidThreadAccess> {
+        if !self.is_valid() {
+            return Err(InvalidThreadAccess::new::<T>(
+                WrapperKind::Sticky,
+                &self.home,
+            ));
+        }
+        self.home.reclaim();
+
+        match self.lookup(exclusive, depth) {
+            Ok(ptr) => Ok(f(ptr)),
+            Err(err) => Err(InvalidThreadAccess::with_reason::<T>(
+                lookup_failure_reason(err),
+                WrapperKind::Sticky,
+                &self.home,
+            )),
         }
     }
 
+    /// Runs `f` with exclusive access to the value for the forwarding impls.
+    ///
+    /// Unlike [`get_mut`](Self::get_mut) this also works while [`StickyWeak`]
+    /// handles exist as long as none of them is upgraded.  They cannot be
+    /// upgraded until `f` returns.
     #[inline(always)]
     #[track_caller]
-    fn with_value<F: FnOnce(*mut T) -> R, R>(&self, f: F) -> R {
+    pub(crate) fn with_mut<F: FnOnce(&mut T) -> R, R>(&mut self, f: F) -> R {
+        crate::stack_token!(tok);
         self.assert_thread();
+        self.home.reclaim();
 
-        registry::with(self.item_id, |entry| f(entry.ptr.cast::<T>()))
+        match registry::lock(self.item_id, tok.depth()) {
+            // SAFETY: the entry stays locked until `f` returned.
+            Ok((ptr, _lock)) => f(unsafe { &mut *ptr.cast::<T>() }),
+            Err(err) => lookup_failed(err),
+        }
     }
 
     /// Returns `true` if the access is valid.
@@ -90,48 +227,221 @@ impl<T> Sticky<T> {
     /// This will be `false` if the value was sent to another thread.
     #[inline(always)]
     pub fn is_valid(&self) -> bool {
-        thread::current().id() == self.thread_id
+        thread_id::is_current(self.thread_id, &self.home)
+    }
+
+    /// Returns the [`Home`] of the thread that wrapped the value.
+    pub fn home(&self) -> &Home {
+        &self.home
+    }
+
+    /// Returns `true` if the thread that wrapped the value is still running.
+    #[inline]
+    pub fn owner_alive(&self) -> bool {
+        self.home.is_alive()
+    }
+
+    /// Returns `true` if the thread that wrapped the value has exited.
+    ///
+    /// The value of an orphaned sticky was already destroyed together with
+    /// the thread local registry of its owner.
+    #[inline]
+    pub fn is_orphaned(&self) -> bool {
+        !self.owner_alive()
+    }
+
+    /// Runs `f` with the wrapped value on the thread that wrapped it.
+    ///
+    /// The [`Sticky`] is moved to its home thread where `f` is invoked the
+    /// next time that thread calls [`pump`](crate::pump).  The returned task
+    /// resolves to the [`Sticky`] together with the return value of `f`.
+    /// If this is called from the home thread, `f` is invoked immediately.
+    pub fn run_on_home<F, R>(self, f: F) -> HomeTask<(Sticky<T>, R)>
+    where
+        F: FnOnce(&mut T) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let home = self.home.clone();
+        home::run_on(&home, self, move |this| {
+            crate::stack_token!(tok);
+            f(this.get_mut(tok))
+        })
+    }
+
+    /// Turns the [`Sticky`] into a reference counted [`StickyRc`].
+    ///
+    /// The returned handle can be cloned to hand out multiple references to
+    /// the value without cloning the value itself.
+    pub fn share(self) -> StickyRc<T> {
+        StickyRc::from_sticky(self)
+    }
+
+    /// Creates a [`StickyWeak`] handle to the value.
+    ///
+    /// Weak handles do not keep the value alive.  While weak handles exist,
+    /// the value can no longer be borrowed mutably, similar to
+    /// [`Rc::get_mut`](std::rc::Rc::get_mut).
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from a different thread than the one where the
+    /// original value was created.
+    #[track_caller]
+    pub fn downgrade(&self) -> StickyWeak<T> {
+        self.assert_thread();
+        match registry::downgrade(self.item_id) {
+            Ok(token) => StickyWeak::new(self.item_id, self.thread_id, self.home.clone(), token),
+            Err(err) => lookup_failed(err),
+        }
+    }
+
+    /// Sets the [`ViolationPolicy`] of this value.
+    ///
+    /// This overrides the global policy for this value.
+    pub fn set_violation_policy(&mut self, policy: ViolationPolicy) {
+        self.policy = Some(policy);
     }
 
     #[inline(always)]
     #[track_caller]
     fn assert_thread(&self) {
         if !self.is_valid() {
+            self.violation(ViolationKind::Access);
             panic!("trying to access wrapped value in sticky container from incorrect thread.");
         }
     }
 
+    /// Consults the violation policy.  Returns normally if it tolerates the violation.
+    #[cold]
+    #[track_caller]
+    pub(crate) fn violation(&self, kind: ViolationKind) {
+        // dropping a sticky on the wrong thread is fine by default.
+        let default = match kind {
+            ViolationKind::Drop => ViolationPolicy::Leak,
+            ViolationKind::Access | ViolationKind::Poll => ViolationPolicy::Panic,
+        };
+        let info = ViolationInfo::new(
+            kind,
+            WrapperKind::Sticky,
+            any::type_name::<T>(),
+            self.home.thread_id(),
+        );
+        policy::handle(self.policy, default, info);
+    }
+
+    /// Discards the handle without releasing its entry.
+    fn forget(self) {
+        let this = mem::ManuallyDrop::new(self);
+        // SAFETY: the destructor of `this` does not run, so the home is only
+        // dropped here.
+        drop(unsafe { std::ptr::read(&this.home) });
+    }
+
     /// Consumes the `Sticky`, returning the wrapped value.
     ///
     /// # Panics
     ///
     /// Panics if called from a different thread than the one where the
-    /// original value was created.
+    /// original value was created or while the value is borrowed through a
+    /// [`StickyWeak`].
     #[track_caller]
-    pub fn into_inner(mut self) -> T {
+    pub fn into_inner(self) -> T {
         self.assert_thread();
-        unsafe {
-            let rv = self.unsafe_take_value();
-            mem::forget(self);
-            rv
-        }
+        self.home.reclaim();
+        let entry = match registry::take(self.item_id) {
+            Ok(entry) => entry,
+            Err(err) => lookup_failed(err),
+        };
+        self.forget();
+        // SAFETY: the entry was removed from the registry so the value is
+        // owned by us now.
+        unsafe { *Box::from_raw(entry.ptr.cast::<T>()) }
+    }
+
+    /// Hands ownership of the value over to another thread.
+    ///
+    /// The value is removed from the registry of the current thread.  The
+    /// returned [`Handoff`] can be sent to other threads, exactly one of which
+    /// can [`claim`](Handoff::claim) a [`Sticky`] whose value is stored in its
+    /// own registry.  [`StickyWeak`] handles to the value are not carried over
+    /// and can no longer be upgraded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from a different thread than the one where the
+    /// original value was created or while the value is borrowed through a
+    /// [`StickyWeak`].
+    #[track_caller]
+    pub fn handoff<M>(self) -> Handoff<Sticky<T>>
+    where
+        T: ThreadMovable<M>,
+    {
+        self.assert_thread();
+        self.home.reclaim();
+        let mut entry = match registry::take(self.item_id) {
+            Ok(entry) => entry,
+            Err(err) => lookup_failed(err),
+        };
+        entry.weak = None;
+        let policy = self.policy;
+        self.forget();
+
+        let parcel = Parcel(Some(entry));
+        Handoff::new(Box::new(move || {
+            let mut parcel = parcel;
+            let entry = parcel.0.take().unwrap();
+            let home = Home::current();
+            home.reclaim();
+            let item_id = match registry::insert(entry) {
+                Ok(item_id) => item_id,
+                Err(entry) => {
+                    drop(Parcel(Some(entry)));
+                    panic!(
+                        "cannot claim sticky value after the thread local registry was destroyed."
+                    )
+                }
+            };
+            Sticky {
+                item_id,
+                thread_id: home.id(),
+                home,
+                policy,
+                _marker: PhantomData,
+            }
+        }))
     }
 
-    unsafe fn unsafe_take_value(&mut self) -> T {
-        let ptr = registry::try_remove(self.item_id).unwrap().ptr.cast::<T>();
-        *Box::from_raw(ptr)
+    /// Converts the `Sticky` into a [`Fragile`] without cloning the value.
+    ///
+    /// The value is moved out of the registry.  As a [`Sticky`] can be
+    /// dropped on any thread, the returned [`Fragile`] hands the value back
+    /// to its home if it is dropped on a foreign thread, like one created
+    /// with [`Fragile::new_deferred`].  The violation policy carries over.
+    ///
+    /// Fails like [`try_into_inner`](Self::try_into_inner) if called from a
+    /// different thread than the one where the original value was created
+    /// or if [`StickyWeak`] handles to the value exist.
+    #[track_caller]
+    pub fn into_fragile(self) -> Result<Fragile<T>, IntoInnerError<Self>> {
+        let policy = self.policy;
+        let mut rv = Fragile::new_deferred(self.try_into_inner()?);
+        if let Some(policy) = policy {
+            rv.set_violation_policy(policy);
+        }
+        Ok(rv)
     }
 
     /// Consumes the `Sticky`, returning the wrapped value if successful.
     ///
     /// The wrapped value is returned if this is called from the same thread
     /// as the one where the original value was created, otherwise the
-    /// `Sticky` is returned as `Err(self)`.
-    pub fn try_into_inner(self) -> Result<T, Self> {
-        if self.is_valid() {
-            Ok(self.into_inner())
-        } else {
-            Err(self)
+    /// `Sticky` is returned as part of the [`IntoInnerError`].
+    #[track_caller]
+    pub fn try_into_inner(self) -> Result<T, IntoInnerError<Self>> {
+        // nothing is borrowed, so the scope of the value does not matter.
+        match self.try_with_value(true, usize::MAX, |_| ()) {
+            Ok(()) => Ok(self.into_inner()),
+            Err(err) => Err(IntoInnerError::new(self, err)),
         }
     }
 
@@ -143,46 +453,99 @@ impl<T> Sticky<T> {
     /// For a non-panicking variant, use [`try_get`](#method.try_get`).
     #[track_caller]
     pub fn get<'stack>(&'stack self, _proof: &'stack StackToken) -> &'stack T {
-        self.with_value(|value| unsafe { &*value })
+        self.with_value(false, _proof.depth(), |value| unsafe { &*value })
     }
 
     /// Mutably borrows the wrapped value.
     ///
     /// # Panics
     ///
-    /// Panics if the calling thread is not the one that wrapped the value.
-    /// For a non-panicking variant, use [`try_get_mut`](#method.try_get_mut`).
+    /// Panics if the calling thread is not the one that wrapped the value or
+    /// if [`StickyWeak`] handles to the value exist.  For a non-panicking
+    /// variant, use [`try_get_mut`](#method.try_get_mut`).
     #[track_caller]
     pub fn get_mut<'stack>(&'stack mut self, _proof: &'stack StackToken) -> &'stack mut T {
-        self.with_value(|value| unsafe { &mut *value })
+        self.with_value(true, _proof.depth(), |value| unsafe { &mut *value })
     }
 
     /// Tries to immutably borrow the wrapped value.
     ///
-    /// Returns `None` if the calling thread is not the one that wrapped the value.
+    /// Returns an error if the calling thread is not the one that wrapped the
+    /// value or if the value was already destroyed together with the thread
+    /// local registry.
+    #[track_caller]
     pub fn try_get<'stack>(
         &'stack self,
         _proof: &'stack StackToken,
     ) -> Result<&'stack T, InvalidThreadAccess> {
-        if self.is_valid() {
-            Ok(self.with_value(|value| unsafe { &*value }))
-        } else {
-            Err(InvalidThreadAccess)
-        }
+        self.try_with_value(false, _proof.depth(), |value| unsafe { &*value })
     }
 
     /// Tries to mutably borrow the wrapped value.
     ///
-    /// Returns `None` if the calling thread is not the one that wrapped the value.
+    /// Returns an error if the calling thread is not the one that wrapped the
+    /// value, if [`StickyWeak`] handles to the value exist or if the value was
+    /// already destroyed together with the thread local registry.
+    #[track_caller]
     pub fn try_get_mut<'stack>(
         &'stack mut self,
         _proof: &'stack StackToken,
     ) -> Result<&'stack mut T, InvalidThreadAccess> {
-        if self.is_valid() {
-            Ok(self.with_value(|value| unsafe { &mut *value }))
-        } else {
-            Err(InvalidThreadAccess)
+        self.try_with_value(true, _proof.depth(), |value| unsafe { &mut *value })
+    }
+}
+
+/// A registry entry in transit to another thread.
+///
+/// The value is `ThreadMovable` so it is destroyed wherever an unclaimed
+/// handoff is dropped.
+struct Parcel(Option<registry::Entry>);
+
+impl Drop for Parcel {
+    fn drop(&mut self) {
+        if let Some(entry) = self.0.take() {
+            // SAFETY: the entry is no longer in any registry.
+            unsafe { (entry.drop)(entry.ptr) };
+        }
+    }
+}
+
+// the entry of a live sticky can only go missing if its scope ended or if the
+// registry destroyed it while the thread shuts down.
+fn lookup_failure_reason(err: registry::LookupError) -> AccessErrorReason {
+    match err {
+        registry::LookupError::Pinned | registry::LookupError::Shared => {
+            AccessErrorReason::Borrowed
         }
+        registry::LookupError::Stale if !registry::is_shutting_down() => {
+            AccessErrorReason::ScopeEnded
+        }
+        registry::LookupError::Destroyed | registry::LookupError::Stale => {
+            AccessErrorReason::RegistryDestroyed
+        }
+        registry::LookupError::OuterToken => AccessErrorReason::OuterToken,
+    }
+}
+
+#[cold]
+#[track_caller]
+fn lookup_failed(err: registry::LookupError) -> ! {
+    match err {
+        registry::LookupError::Pinned => panic!(
+            "trying to take or mutably access wrapped value in sticky container while it is borrowed through a weak handle."
+        ),
+        registry::LookupError::Shared => panic!(
+            "trying to mutably access wrapped value in sticky container while weak handles exist."
+        ),
+        registry::LookupError::Stale if !registry::is_shutting_down() => panic!(
+            "trying to access wrapped value in sticky container after its scope ended."
+        ),
+        registry::LookupError::Destroyed | registry::LookupError::Stale => panic!(
+            "trying to access wrapped value in sticky container after the thread local registry was destroyed."
+        ),
+        registry::LookupError::OuterToken => panic!(
+            "trying to borrow wrapped value in sticky container with a stack token from outside of its scope."
+        ),
     }
 }
 
@@ -193,12 +556,24 @@ impl<T> From<T> for Sticky<T> {
     }
 }
 
+impl<T> TryFrom<Fragile<T>> for Sticky<T> {
+    type Error = IntoInnerError<Fragile<T>>;
+
+    /// Converts with [`Fragile::into_sticky`].
+    #[track_caller]
+    fn try_from(value: Fragile<T>) -> Result<Sticky<T>, IntoInnerError<Fragile<T>>> {
+        value.into_sticky()
+    }
+}
+
 impl<T: Clone> Clone for Sticky<T> {
     #[inline]
     #[track_caller]
     fn clone(&self) -> Sticky<T> {
         crate::stack_token!(tok);
-        Sticky::new(self.get(tok).clone())
+        let mut rv = Sticky::new(self.get(tok).clone());
+        rv.policy = self.policy;
+        rv
     }
 }
 
@@ -295,6 +670,140 @@ impl<T: fmt::Debug> fmt::Debug for Sticky<T> {
     }
 }
 
+impl<T: Hash> Hash for Sticky<T> {
+    #[inline]
+    #[track_caller]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        crate::stack_token!(tok);
+        self.get(tok).hash(state)
+    }
+}
+
+impl<T: Iterator> Iterator for Sticky<T> {
+    type Item = T::Item;
+
+    #[inline]
+    #[track_caller]
+    fn next(&mut self) -> Option<T::Item> {
+        self.with_mut(|x| x.next())
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        crate::stack_token!(tok);
+        match self.try_get(tok) {
+            Ok(x) => x.size_hint(),
+            Err(_) => (0, None),
+        }
+    }
+}
+
+impl<T: DoubleEndedIterator> DoubleEndedIterator for Sticky<T> {
+    #[inline]
+    #[track_caller]
+    fn next_back(&mut self) -> Option<T::Item> {
+        self.with_mut(|x| x.next_back())
+    }
+}
+
+impl<T: ExactSizeIterator> ExactSizeIterator for Sticky<T> {
+    #[inline]
+    #[track_caller]
+    fn len(&self) -> usize {
+        crate::stack_token!(tok);
+        self.get(tok).len()
+    }
+}
+
+impl<T: io::Read> io::Read for Sticky<T> {
+    #[inline]
+    #[track_caller]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.with_mut(|x| x.read(buf))
+    }
+
+    #[inline]
+    #[track_caller]
+    fn read_vectored(&mut self, bufs: &mut [io::IoSliceMut<'_>]) -> io::Result<usize> {
+        self.with_mut(|x| x.read_vectored(bufs))
+    }
+}
+
+impl<T: io::Write> io::Write for Sticky<T> {
+    #[inline]
+    #[track_caller]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.with_mut(|x| x.write(buf))
+    }
+
+    #[inline]
+    #[track_caller]
+    fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+        self.with_mut(|x| x.write_vectored(bufs))
+    }
+
+    #[inline]
+    #[track_caller]
+    fn flush(&mut self) -> io::Result<()> {
+        self.with_mut(|x| x.flush())
+    }
+}
+
+impl<T: io::Seek> io::Seek for Sticky<T> {
+    #[inline]
+    #[track_caller]
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.with_mut(|x| x.seek(pos))
+    }
+}
+
+// the source cannot be forwarded as the borrow could outlive the registry of
+// the owning thread.
+impl<T: error::Error> error::Error for Sticky<T> {}
+
+impl<T: error::Error> Sticky<T> {
+    /// Returns the source of the wrapped error.
+    ///
+    /// The `Error` implementation of a [`Sticky`] cannot forward
+    /// [`source`](error::Error::source) as the borrow could outlive the
+    /// registry of the owning thread, so it always returns `None`.  This
+    /// borrows the source with a [`StackToken`] instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from a different thread than the one where the
+    /// original value was created.
+    #[track_caller]
+    pub fn source_with<'stack>(
+        &'stack self,
+        _proof: &'stack StackToken,
+    ) -> Option<&'stack (dyn error::Error + 'static)> {
+        self.get(_proof).source()
+    }
+}
+
+impl<T: io::BufRead> Sticky<T> {
+    /// Returns the buffer of the wrapped reader.
+    ///
+    /// A [`Sticky`] cannot implement [`BufRead`](io::BufRead) for the same
+    /// reason it cannot forward the source of an error.  This borrows the
+    /// buffer with a [`StackToken`] instead.  Consume from it through
+    /// [`get_mut`](Self::get_mut).
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from a different thread than the one where the
+    /// original value was created or if [`StickyWeak`] handles to the
+    /// value exist as the buffer borrows it.
+    #[track_caller]
+    pub fn fill_buf_with<'stack>(
+        &'stack mut self,
+        _proof: &'stack StackToken,
+    ) -> io::Result<&'stack [u8]> {
+        self.get_mut(_proof).fill_buf()
+    }
+}
+
 // similar as for fragile the type is sync because it only accesses TLS data
 // which is thread local.  There is nothing that needs to be synchronized.
 unsafe impl<T> Sync for Sticky<T> {}
@@ -439,3 +948,407 @@ fn test_thread_spawn() {
     drop(dummy_sticky);
     assert_eq!(hello, "Hello World");
 }
+
+#[test]
+fn test_run_on_home() {
+    use std::cell::RefCell;
+    use std::sync::mpsc::channel;
+    use std::thread;
+
+    let val = Sticky::new(RefCell::new(vec![1, 2]));
+    let (tx, rx) = channel();
+    let thread = thread::spawn(move || {
+        let task = val.run_on_home(|value| {
+            value.borrow_mut().push(3);
+            value.borrow().len()
+        });
+        tx.send(()).unwrap();
+        task.wait().unwrap()
+    });
+
+    rx.recv().unwrap();
+    assert_eq!(crate::pump(), 1);
+    let (val, rv) = thread.join().unwrap();
+    crate::stack_token!(tok);
+    assert_eq!(rv, 3);
+    assert_eq!(*val.get(tok).borrow(), vec![1, 2, 3]);
+}
+
+#[test]
+fn test_reclaim_drop_elsewhere() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    let was_called = Arc::new(AtomicBool::new(false));
+    struct X(Arc<AtomicBool>);
+    impl Drop for X {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    let val = Sticky::new(X(was_called.clone()));
+    thread::spawn(move || {
+        crate::stack_token!(tok);
+        val.try_get(tok).ok();
+    })
+    .join()
+    .unwrap();
+    assert!(!was_called.load(Ordering::SeqCst));
+
+    // creating another sticky reclaims the entry
+    let _other = Sticky::new(X(Arc::new(AtomicBool::new(false))));
+    assert!(was_called.load(Ordering::SeqCst));
+}
+
+#[test]
+fn test_panic_policy_drop_elsewhere() {
+    use std::thread;
+    struct X;
+    impl Drop for X {
+        fn drop(&mut self) {}
+    }
+    let mut val = Sticky::new(X);
+    val.set_violation_policy(ViolationPolicy::Panic);
+    assert!(thread::spawn(move || {
+        crate::stack_token!(tok);
+        val.try_get(tok).ok();
+    })
+    .join()
+    .is_err());
+}
+
+#[test]
+fn test_orphaned_drop() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    let was_called = Arc::new(AtomicBool::new(false));
+    struct X(Arc<AtomicBool>);
+    impl Drop for X {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+    let was_called2 = was_called.clone();
+    let mut val = thread::spawn(move || Sticky::new(X(was_called2)))
+        .join()
+        .unwrap();
+    assert!(val.is_orphaned());
+    assert!(!val.owner_alive());
+    // the registry of the owner already destroyed the value.
+    assert!(was_called.load(Ordering::SeqCst));
+    val.set_violation_policy(ViolationPolicy::Panic);
+    drop(val);
+}
+
+#[test]
+fn test_nested_teardown() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    struct X(Arc<AtomicUsize>);
+    impl Drop for X {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let drops = Arc::new(AtomicUsize::new(0));
+    let drops2 = drops.clone();
+    let val = thread::spawn(move || {
+        let inner = Sticky::new(X(drops2.clone()));
+        let outer = Sticky::new((inner, X(drops2.clone())));
+        mem::forget(Sticky::new(Sticky::new(Sticky::new(X(drops2)))));
+        outer
+    })
+    .join()
+    .unwrap();
+    assert_eq!(drops.load(Ordering::SeqCst), 3);
+    drop(val);
+}
+
+#[test]
+fn test_created_in_destructor() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    struct X(Arc<AtomicUsize>);
+    impl Drop for X {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    struct Y(Arc<AtomicUsize>);
+    impl Drop for Y {
+        fn drop(&mut self) {
+            mem::forget(Sticky::new(X(self.0.clone())));
+            drop(Sticky::new(X(self.0.clone())));
+        }
+    }
+
+    let drops = Arc::new(AtomicUsize::new(0));
+    drop(Sticky::new(Y(drops.clone())));
+    assert_eq!(drops.load(Ordering::SeqCst), 1);
+
+    let drops2 = drops.clone();
+    thread::spawn(move || mem::forget(Sticky::new(Y(drops2))))
+        .join()
+        .unwrap();
+    assert_eq!(drops.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn test_access_in_destructor() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use std::thread;
+
+    struct Z(Sticky<Rc<Cell<bool>>>);
+    impl Drop for Z {
+        fn drop(&mut self) {
+            crate::stack_token!(tok);
+            self.0.get(tok).set(true);
+            // access and create other values while the registry is in use.
+            let other = Sticky::new(42);
+            assert_eq!(*other.get(tok), 42);
+        }
+    }
+
+    let flag = Rc::new(Cell::new(false));
+    drop(Sticky::new(Z(Sticky::new(flag.clone()))));
+    assert!(flag.get());
+
+    thread::spawn(|| {
+        mem::forget(Sticky::new(Z(Sticky::new(Rc::new(Cell::new(false))))));
+    })
+    .join()
+    .unwrap();
+}
+
+#[test]
+fn test_rc_cycle() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    struct Node {
+        next: RefCell<Option<Sticky<Rc<Node>>>>,
+        drops: Arc<AtomicUsize>,
+    }
+    impl Drop for Node {
+        fn drop(&mut self) {
+            self.drops.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let drops = Arc::new(AtomicUsize::new(0));
+    let drops2 = drops.clone();
+    thread::spawn(move || {
+        let a = Rc::new(Node {
+            next: RefCell::new(None),
+            drops: drops2.clone(),
+        });
+        let b = Rc::new(Node {
+            next: RefCell::new(Some(Sticky::new(a.clone()))),
+            drops: drops2,
+        });
+        *a.next.borrow_mut() = Some(Sticky::new(b));
+        let _self_cycle = Sticky::new(a.clone());
+    })
+    .join()
+    .unwrap();
+    assert_eq!(drops.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_inside_thread_local() {
+    use crate::AccessErrorReason;
+    use std::cell::RefCell;
+    use std::sync::mpsc::{channel, Sender};
+    use std::thread;
+
+    type Payload = (Sticky<String>, Sender<(bool, bool)>);
+    struct Holder(RefCell<Option<Payload>>);
+    impl Drop for Holder {
+        fn drop(&mut self) {
+            let (val, tx) = self.0.borrow_mut().take().unwrap();
+            crate::stack_token!(tok);
+            // depending on the platform the registry might already be gone.
+            let accessed = match val.try_get(tok) {
+                Ok(value) => value == "hello",
+                Err(err) => err.reason() == AccessErrorReason::RegistryDestroyed,
+            };
+            let created = match Sticky::try_new(42) {
+                Ok(new) => *new.get(tok) == 42,
+                Err(err) => err.reason() == AccessErrorReason::RegistryDestroyed,
+            };
+            drop(val);
+            tx.send((accessed, created)).unwrap();
+        }
+    }
+
+    thread_local!(static HOLDER: Holder = Holder(RefCell::new(None)));
+
+    let (tx, rx) = channel();
+    thread::spawn(move || {
+        // the holder is registered first so that its destructor runs after
+        // the registry was destroyed on platforms that destroy thread locals
+        // in reverse order.
+        HOLDER.with(|holder| {
+            *holder.0.borrow_mut() = Some((Sticky::new("hello".to_string()), tx));
+        });
+    })
+    .join()
+    .unwrap();
+    assert_eq!(rx.recv().unwrap(), (true, true));
+}
+
+#[test]
+fn test_std_traits() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::io::{BufRead, Cursor, Read, Seek, SeekFrom, Write};
+
+    crate::stack_token!(tok);
+    let mut iter = Sticky::new(vec![1, 2, 3].into_iter());
+    assert_eq!(iter.size_hint(), (3, Some(3)));
+    assert_eq!(iter.next_back(), Some(3));
+    assert_eq!(iter.len(), 2);
+    assert_eq!(iter.next(), Some(1));
+
+    let mut cursor = Sticky::new(Cursor::new(Vec::new()));
+    cursor.write_all(b"hello").unwrap();
+    cursor.seek(SeekFrom::Start(1)).unwrap();
+    let mut buf = String::new();
+    cursor.read_to_string(&mut buf).unwrap();
+    assert_eq!(buf, "ello");
+    assert_eq!(cursor.get(tok).get_ref(), b"hello");
+
+    let mut a = DefaultHasher::new();
+    let mut b = DefaultHasher::new();
+    Sticky::new(42).hash(&mut a);
+    42.hash(&mut b);
+    assert_eq!(a.finish(), b.finish());
+
+    let err: Box<dyn error::Error> = Box::new(Sticky::new(std::fmt::Error));
+    assert_eq!(err.to_string(), std::fmt::Error.to_string());
+    assert!(err.source().is_none());
+
+    #[derive(Debug)]
+    struct Outer(std::fmt::Error);
+    impl std::fmt::Display for Outer {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "outer")
+        }
+    }
+    impl error::Error for Outer {
+        fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+    let err = Sticky::new(Outer(std::fmt::Error));
+    assert!(err.source_with(tok).unwrap().is::<std::fmt::Error>());
+
+    let mut reader = Sticky::new(Cursor::new(b"hello".to_vec()));
+    assert_eq!(reader.fill_buf_with(tok).unwrap(), b"hello");
+    reader.get_mut(tok).consume(2);
+    assert_eq!(reader.fill_buf_with(tok).unwrap(), b"llo");
+}
+
+#[test]
+fn test_handoff() {
+    use std::rc::Rc;
+    use std::thread;
+
+    struct Job(Rc<u32>);
+    unsafe impl crate::ThreadMovable for Job {}
+
+    let before = registry::stats().entries();
+    let val = Sticky::new(Job(Rc::new(21)));
+    let _weak = val.downgrade();
+    let handoff = val.handoff();
+    assert_eq!(registry::stats().entries(), before);
+
+    let value = thread::spawn(move || {
+        crate::stack_token!(tok);
+        let mut val = handoff.claim().unwrap();
+        assert_eq!(registry::stats().entries(), 1);
+        // weak handles stay behind, so the value is exclusively owned.
+        val.get_mut(tok).0 = Rc::new(42);
+        let value = *val.get(tok).0;
+        drop(val);
+        assert_eq!(registry::stats().entries(), 0);
+        value
+    })
+    .join()
+    .unwrap();
+    assert_eq!(value, 42);
+}
+
+#[test]
+fn test_into_inner_releases_home() {
+    use std::thread;
+
+    let home = Home::current();
+    let before = home.strong_count();
+    let val = Sticky::new(vec![1, 2, 3]);
+    assert_eq!(home.strong_count(), before + 1);
+    assert_eq!(val.into_inner(), vec![1, 2, 3]);
+    assert_eq!(home.strong_count(), before);
+
+    let handoff = Sticky::new(vec![1, 2, 3]).handoff();
+    assert_eq!(home.strong_count(), before);
+    thread::spawn(move || handoff.claim().unwrap().into_inner())
+        .join()
+        .unwrap();
+    assert_eq!(home.strong_count(), before);
+}
+
+#[test]
+fn test_forwarding_with_weak() {
+    use std::cell::RefCell;
+    use std::io::Write;
+    use std::rc::Rc;
+
+    type Iter = Box<dyn DoubleEndedIterator<Item = bool>>;
+    let slot = Rc::new(RefCell::new(None::<StickyWeak<Iter>>));
+    let inner_slot = slot.clone();
+    let mut iter: Sticky<Iter> = Sticky::new(Box::new((0..2).map(move |_| {
+        crate::stack_token!(tok);
+        let weak = inner_slot.borrow();
+        let rv = weak.as_ref().unwrap().upgrade(tok).is_none();
+        rv
+    })));
+    *slot.borrow_mut() = Some(iter.downgrade());
+
+    // the weak handle cannot alias the value while the iterator advances.
+    assert_eq!(iter.next(), Some(true));
+    assert_eq!(iter.next_back(), Some(true));
+    assert_eq!(iter.next(), None);
+
+    crate::stack_token!(tok);
+    let weak = slot.borrow_mut().take().unwrap();
+    assert!(weak.upgrade(tok).is_some());
+    assert!(iter.try_get_mut(tok).is_err());
+
+    let mut writer = Sticky::new(Vec::new());
+    let weak = writer.downgrade();
+    writer.write_all(b"hello").unwrap();
+    assert_eq!(&weak.upgrade(tok).unwrap()[..], b"hello");
+
+    {
+        let _pinned = weak.upgrade(tok).unwrap();
+        assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            writer.write_all(b"!").unwrap();
+        }))
+        .is_err());
+    }
+    writer.write_all(b"!").unwrap();
+    assert_eq!(writer.get(tok), b"hello!");
+}
@@ -0,0 +1,281 @@
+use std::any;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::num::NonZeroUsize;
+use std::panic::Location;
+use std::thread::ThreadId;
+
+use crate::errors::{AccessErrorReason, InvalidThreadAccess, WrapperKind};
+use crate::home::{self, Home};
+use crate::registry;
+use crate::stickyweak::StickyRef;
+use crate::StackToken;
+
+/// A copyable key to a value stored in the registry of a thread.
+///
+/// A [`StickyKey`] works like a [`Sticky`](crate::Sticky) without drop
+/// semantics.  The value is moved into the registry of the current thread and
+/// the key can be freely copied, sent to other threads, hashed and compared.
+/// Only the thread that created the key can redeem it, either by borrowing the
+/// value with [`get`](Self::get) or by moving it out with
+/// [`take`](Self::take).
+///
+/// As keys do not own the value, it is not destroyed when the keys go away.
+/// A value that is never taken stays in the registry until the thread shuts
+/// down.  Such values show up in [`registry::stats`](crate::registry::stats).
+///
+/// ```
+/// use fragile::StickyKey;
+///
+/// fragile::stack_token!(tok);
+/// let key = StickyKey::new(String::from("hello"));
+/// let copy = key;
+/// assert_eq!(&*key.get(tok), "hello");
+/// assert_eq!(copy.take(), "hello");
+/// assert!(key.try_take().is_err());
+/// ```
+pub struct StickyKey<T: 'static> {
+    item_id: registry::ItemId,
+    thread_id: NonZeroUsize,
+    owner: ThreadId,
+    _marker: PhantomData<*mut T>,
+}
+
+impl<T> StickyKey<T> {
+    /// Moves `value` into the registry of the current thread and returns a
+    /// key to it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the thread local registry of the current thread was already
+    /// destroyed.  This can only happen if this is called from the destructor
+    /// of a thread local.
+    #[track_caller]
+    pub fn new(value: T) -> StickyKey<T> {
+        let entry = registry::Entry::new(value, Location::caller());
+
+        let home = Home::current();
+        home.reclaim();
+        let item_id = match registry::insert(entry) {
+            Ok(item_id) => item_id,
+            Err(entry) => {
+                // SAFETY: the entry never made it into the registry.
+                unsafe { (entry.drop)(entry.ptr) };
+                panic!("cannot create sticky key after the thread local registry was destroyed.");
+            }
+        };
+
+        StickyKey {
+            item_id,
+            thread_id: home.id(),
+            owner: home.thread_id(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns `true` if the key can be redeemed on the current thread.
+    ///
+    /// This will be `false` if the key was sent to another thread.  It does
+    /// not tell if the value was taken already.
+    #[inline(always)]
+    pub fn is_valid(self) -> bool {
+        // the cached id can only be unavailable while the thread shuts down
+        // in which case the registry is gone as well.
+        crate::thread_id::try_get() == Some(self.thread_id)
+    }
+
+    /// Returns the ID of the thread that created the key.
+    pub fn owner(self) -> ThreadId {
+        self.owner
+    }
+
+    #[cold]
+    #[track_caller]
+    fn error(self, reason: AccessErrorReason) -> InvalidThreadAccess {
+        InvalidThreadAccess::for_owner::<T>(reason, WrapperKind::StickyKey, self.owner, None)
+    }
+
+    #[track_caller]
+    fn check(self) -> Result<(), InvalidThreadAccess> {
+        if self.is_valid() {
+            home::reclaim();
+            Ok(())
+        } else {
+            Err(self.error(AccessErrorReason::WrongThread))
+        }
+    }
+
+    #[cold]
+    #[track_caller]
+    fn lookup_failed(self, err: registry::LookupError) -> InvalidThreadAccess {
+        self.error(match err {
+            registry::LookupError::Destroyed => AccessErrorReason::RegistryDestroyed,
+            registry::LookupError::Stale => AccessErrorReason::AlreadyTaken,
+            registry::LookupError::OuterToken => AccessErrorReason::OuterToken,
+            registry::LookupError::Pinned | registry::LookupError::Shared => {
+                AccessErrorReason::Borrowed
+            }
+        })
+    }
+
+    /// Borrows the value.
+    ///
+    /// While the returned [`StickyRef`] exists the value cannot be taken.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the calling thread is not the one that created the key or
+    /// if the value was taken already.  For a non-panicking variant, use
+    /// [`try_get`](#method.try_get).
+    #[track_caller]
+    pub fn get<'stack>(self, proof: &'stack StackToken) -> StickyRef<'stack, T> {
+        match self.try_get(proof) {
+            Ok(rv) => rv,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Tries to borrow the value.
+    ///
+    /// Returns an error if the calling thread is not the one that created
+    /// the key or if the value was taken already.
+    #[track_caller]
+    pub fn try_get<'stack>(
+        self,
+        _proof: &'stack StackToken,
+    ) -> Result<StickyRef<'stack, T>, InvalidThreadAccess> {
+        self.check()?;
+        match registry::pin(self.item_id, _proof.depth()) {
+            // SAFETY: the entry was pinned for us.
+            Ok(ptr) => Ok(unsafe { StickyRef::new(self.item_id, ptr.cast()) }),
+            Err(err) => Err(self.lookup_failed(err)),
+        }
+    }
+
+    /// Moves the value out of the registry.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the calling thread is not the one that created the key, if
+    /// the value was taken already or if it is currently borrowed.  For a
+    /// non-panicking variant, use [`try_take`](#method.try_take).
+    #[track_caller]
+    pub fn take(self) -> T {
+        match self.try_take() {
+            Ok(rv) => rv,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Tries to move the value out of the registry.
+    ///
+    /// Returns an error if the calling thread is not the one that created
+    /// the key, if the value was taken already or if it is currently
+    /// borrowed.
+    #[track_caller]
+    pub fn try_take(self) -> Result<T, InvalidThreadAccess> {
+        self.check()?;
+        match registry::take(self.item_id) {
+            // SAFETY: the entry was removed from the registry so the value
+            // is owned by us now.
+            Ok(entry) => Ok(unsafe { *Box::from_raw(entry.ptr.cast::<T>()) }),
+            Err(err) => Err(self.lookup_failed(err)),
+        }
+    }
+}
+
+impl<T> Clone for StickyKey<T> {
+    #[inline]
+    fn clone(&self) -> StickyKey<T> {
+        *self
+    }
+}
+
+impl<T> Copy for StickyKey<T> {}
+
+impl<T> PartialEq for StickyKey<T> {
+    #[inline]
+    fn eq(&self, other: &StickyKey<T>) -> bool {
+        self.thread_id == other.thread_id && self.item_id == other.item_id
+    }
+}
+
+impl<T> Eq for StickyKey<T> {}
+
+impl<T> Hash for StickyKey<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.thread_id.hash(state);
+        self.item_id.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for StickyKey<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("StickyKey")
+            .field("owner", &self.owner)
+            .field("type_name", &any::type_name::<T>())
+            .finish()
+    }
+}
+
+// the key never touches the value outside of the owning thread.
+unsafe impl<T> Sync for StickyKey<T> {}
+
+// The entire point of this type is to be Send
+unsafe impl<T> Send for StickyKey<T> {}
+
+#[test]
+fn test_get_and_take() {
+    use crate::AccessErrorReason;
+    use std::collections::HashSet;
+    use std::rc::Rc;
+    use std::thread;
+
+    crate::stack_token!(tok);
+    let key = StickyKey::new(Rc::new(42));
+    let other = StickyKey::new(Rc::new(23));
+    let mut keys = HashSet::new();
+    keys.insert(key);
+    keys.insert(key);
+    keys.insert(other);
+    assert_eq!(keys.len(), 2);
+
+    thread::spawn(move || {
+        crate::stack_token!(tok);
+        assert!(!key.is_valid());
+        let err = key.try_get(tok).unwrap_err();
+        assert_eq!(err.reason(), AccessErrorReason::WrongThread);
+        assert_eq!(err.wrapper(), WrapperKind::StickyKey);
+        assert!(key.try_take().is_err());
+    })
+    .join()
+    .unwrap();
+
+    {
+        let borrowed = key.get(tok);
+        assert_eq!(**borrowed, 42);
+        let err = key.try_take().unwrap_err();
+        assert_eq!(err.reason(), AccessErrorReason::Borrowed);
+    }
+    assert_eq!(*key.take(), 42);
+    let err = key.try_get(tok).unwrap_err();
+    assert_eq!(err.reason(), AccessErrorReason::AlreadyTaken);
+    assert_eq!(*other.take(), 23);
+}
+
+#[test]
+fn test_message_passing() {
+    use std::sync::mpsc::channel;
+    use std::thread;
+
+    let (tx, rx) = channel();
+    let (back_tx, back_rx) = channel();
+    let worker = thread::spawn(move || {
+        let key: StickyKey<Vec<i32>> = rx.recv().unwrap();
+        back_tx.send(key).unwrap();
+    });
+    tx.send(StickyKey::new(vec![1, 2, 3])).unwrap();
+    worker.join().unwrap();
+    assert_eq!(back_rx.recv().unwrap().take(), vec![1, 2, 3]);
+}
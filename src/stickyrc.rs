@@ -0,0 +1,194 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::errors::InvalidThreadAccess;
+use crate::home::Home;
+use crate::sticky::Sticky;
+use crate::stickyweak::StickyWeak;
+use crate::StackToken;
+
+/// A reference counted handle to a value stored in a [`Sticky`].
+///
+/// Unlike cloning a [`Sticky`], which clones the wrapped value, cloning a
+/// [`StickyRc`] creates another handle to the same value.  The handles are
+/// `Send`, `Sync` and `Clone` and can be passed around freely, but only the
+/// thread that wrapped the value can access it.  Because the value is shared
+/// it can only be borrowed immutably.
+///
+/// The value is destroyed when the last handle is dropped.  If that happens
+/// on a foreign thread, it is reclaimed by the owning thread just like a
+/// [`Sticky`] dropped on a foreign thread.
+///
+/// ```
+/// use fragile::StickyRc;
+///
+/// fragile::stack_token!(tok);
+/// let a = StickyRc::new(vec![1, 2, 3]);
+/// let b = a.clone();
+/// assert!(StickyRc::ptr_eq(&a, &b));
+/// assert_eq!(b.get(tok).len(), 3);
+/// ```
+pub struct StickyRc<T: 'static> {
+    inner: Arc<Sticky<T>>,
+}
+
+impl<T> StickyRc<T> {
+    /// Creates a new [`StickyRc`] wrapping a `value`.
+    #[track_caller]
+    pub fn new(value: T) -> Self {
+        Sticky::new(value).share()
+    }
+
+    pub(crate) fn from_sticky(sticky: Sticky<T>) -> Self {
+        StickyRc {
+            inner: Arc::new(sticky),
+        }
+    }
+
+    /// Returns `true` if the access is valid.
+    ///
+    /// This will be `false` if the handle was sent to another thread.
+    #[inline(always)]
+    pub fn is_valid(&self) -> bool {
+        self.inner.is_valid()
+    }
+
+    /// Returns the [`Home`] of the thread that wrapped the value.
+    pub fn home(&self) -> &Home {
+        self.inner.home()
+    }
+
+    /// Returns `true` if the thread that wrapped the value is still running.
+    pub fn owner_alive(&self) -> bool {
+        self.inner.owner_alive()
+    }
+
+    /// Returns `true` if the thread that wrapped the value has exited.
+    pub fn is_orphaned(&self) -> bool {
+        self.inner.is_orphaned()
+    }
+
+    /// Immutably borrows the wrapped value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the calling thread is not the one that wrapped the value.
+    /// For a non-panicking variant, use [`try_get`](#method.try_get`).
+    #[track_caller]
+    pub fn get<'stack>(&'stack self, proof: &'stack StackToken) -> &'stack T {
+        self.inner.get(proof)
+    }
+
+    /// Tries to immutably borrow the wrapped value.
+    ///
+    /// Returns an error if the calling thread is not the one that wrapped the value.
+    #[track_caller]
+    pub fn try_get<'stack>(
+        &'stack self,
+        proof: &'stack StackToken,
+    ) -> Result<&'stack T, InvalidThreadAccess> {
+        self.inner.try_get(proof)
+    }
+
+    /// Returns the number of handles to the value.
+    pub fn strong_count(this: &Self) -> usize {
+        Arc::strong_count(&this.inner)
+    }
+
+    /// Returns `true` if both handles point to the same value.
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Arc::ptr_eq(&this.inner, &other.inner)
+    }
+
+    /// Creates a [`StickyWeak`] handle to the value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from a different thread than the one where the
+    /// original value was created.
+    #[track_caller]
+    pub fn downgrade(this: &Self) -> StickyWeak<T> {
+        this.inner.downgrade()
+    }
+
+    /// Returns the [`Sticky`] if this is the only handle to the value.
+    ///
+    /// Otherwise the handle is returned unchanged.
+    pub fn try_unwrap(this: Self) -> Result<Sticky<T>, Self> {
+        Arc::try_unwrap(this.inner).map_err(|inner| StickyRc { inner })
+    }
+}
+
+impl<T> Clone for StickyRc<T> {
+    #[inline]
+    fn clone(&self) -> StickyRc<T> {
+        StickyRc {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> From<Sticky<T>> for StickyRc<T> {
+    #[inline]
+    fn from(sticky: Sticky<T>) -> StickyRc<T> {
+        sticky.share()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for StickyRc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        fmt::Debug::fmt(&*self.inner, f)
+    }
+}
+
+#[test]
+fn test_basic() {
+    use std::thread;
+    let val = StickyRc::new(true);
+    let other = val.clone();
+    crate::stack_token!(tok);
+    assert!(*other.get(tok));
+    assert_eq!(StickyRc::strong_count(&val), 2);
+    thread::spawn(move || {
+        crate::stack_token!(tok);
+        assert!(other.try_get(tok).is_err());
+        assert!(!other.is_valid());
+    })
+    .join()
+    .unwrap();
+    assert_eq!(StickyRc::strong_count(&val), 1);
+    let sticky = StickyRc::try_unwrap(val).unwrap();
+    assert!(sticky.into_inner());
+}
+
+#[test]
+fn test_last_drop_elsewhere() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread;
+
+    let was_called = Arc::new(AtomicBool::new(false));
+    struct X(Arc<AtomicBool>);
+    impl Drop for X {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    let val = StickyRc::new(X(was_called.clone()));
+    let handles: Vec<_> = (0..4).map(|_| val.clone()).collect();
+    drop(val);
+    thread::spawn(move || {
+        let mut threads = Vec::new();
+        for handle in handles {
+            threads.push(thread::spawn(move || drop(handle)));
+        }
+        for thread in threads {
+            thread.join().unwrap();
+        }
+    })
+    .join()
+    .unwrap();
+    assert!(!was_called.load(Ordering::SeqCst));
+    crate::pump();
+    assert!(was_called.load(Ordering::SeqCst));
+}
@@ -0,0 +1,233 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::num::NonZeroUsize;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use crate::home::Home;
+use crate::registry;
+use crate::thread_id;
+use crate::StackToken;
+
+/// A weak handle to the value of a [`Sticky`](crate::Sticky).
+///
+/// Created with [`Sticky::downgrade`](crate::Sticky::downgrade) or
+/// [`StickyRc::downgrade`](crate::StickyRc::downgrade).  A weak handle does
+/// not keep the value alive and can be sent to and dropped on any thread.  It
+/// can only be upgraded on the thread that wrapped the value, as long as the
+/// value was not dropped yet.
+///
+/// ```
+/// use fragile::Sticky;
+///
+/// fragile::stack_token!(tok);
+/// let val = Sticky::new(vec![1, 2, 3]);
+/// let weak = val.downgrade();
+/// assert_eq!(weak.upgrade(tok).unwrap().len(), 3);
+/// drop(val);
+/// assert!(weak.upgrade(tok).is_none());
+/// ```
+pub struct StickyWeak<T: 'static> {
+    item_id: registry::ItemId,
+    thread_id: NonZeroUsize,
+    home: Home,
+    token: Arc<()>,
+    _marker: PhantomData<*mut T>,
+}
+
+impl<T> StickyWeak<T> {
+    pub(crate) fn new(
+        item_id: registry::ItemId,
+        thread_id: NonZeroUsize,
+        home: Home,
+        token: Arc<()>,
+    ) -> StickyWeak<T> {
+        StickyWeak {
+            item_id,
+            thread_id,
+            home,
+            token,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns `true` if the handle can be upgraded on the current thread.
+    ///
+    /// This will be `false` if the handle was sent to another thread.
+    #[inline(always)]
+    pub fn is_valid(&self) -> bool {
+        thread_id::is_current(self.thread_id, &self.home)
+    }
+
+    /// Returns the [`Home`] of the thread that wrapped the value.
+    pub fn home(&self) -> &Home {
+        &self.home
+    }
+
+    /// Borrows the value if it is still alive.
+    ///
+    /// Returns `None` if the value was dropped or if the calling thread is
+    /// not the one that wrapped the value.  While the returned [`StickyRef`]
+    /// exists the value stays alive even if its owner is dropped.
+    pub fn upgrade<'stack>(
+        &'stack self,
+        _proof: &'stack StackToken,
+    ) -> Option<StickyRef<'stack, T>> {
+        if !self.is_valid() {
+            return None;
+        }
+        self.home.reclaim();
+        // SAFETY: the entry was pinned for us.
+        registry::pin(self.item_id, _proof.depth())
+            .ok()
+            .map(|ptr| unsafe { StickyRef::new(self.item_id, ptr.cast()) })
+    }
+}
+
+impl<T> Clone for StickyWeak<T> {
+    #[inline]
+    fn clone(&self) -> StickyWeak<T> {
+        StickyWeak {
+            item_id: self.item_id,
+            thread_id: self.thread_id,
+            home: self.home.clone(),
+            token: self.token.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for StickyWeak<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("(StickyWeak)")
+    }
+}
+
+// the handle never touches the value outside of the owning thread.
+unsafe impl<T> Sync for StickyWeak<T> {}
+
+// The entire point of this type is to be Send
+unsafe impl<T> Send for StickyWeak<T> {}
+
+/// A borrow of a value obtained through [`StickyWeak::upgrade`] or
+/// [`StickyKey::get`](crate::StickyKey::get).
+///
+/// The value is kept alive for as long as the borrow exists.
+pub struct StickyRef<'stack, T: 'static> {
+    item_id: registry::ItemId,
+    ptr: *const T,
+    _marker: PhantomData<&'stack T>,
+}
+
+impl<'stack, T> StickyRef<'stack, T> {
+    /// # Safety
+    ///
+    /// The entry must have been pinned for this borrow.
+    pub(crate) unsafe fn new(item_id: registry::ItemId, ptr: *const T) -> StickyRef<'stack, T> {
+        StickyRef {
+            item_id,
+            ptr,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'stack, T> Deref for StickyRef<'stack, T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &T {
+        // SAFETY: the entry is pinned which keeps the value alive and
+        // prevents it from being borrowed mutably or moved out.
+        unsafe { &*self.ptr }
+    }
+}
+
+impl<'stack, T> Drop for StickyRef<'stack, T> {
+    fn drop(&mut self) {
+        if let Some(entry) = registry::unpin(self.item_id) {
+            // SAFETY: the owner was dropped while the entry was pinned and
+            // the entry was just removed.
+            unsafe { (entry.drop)(entry.ptr) };
+        }
+    }
+}
+
+impl<'stack, T: fmt::Debug> fmt::Debug for StickyRef<'stack, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[test]
+fn test_upgrade() {
+    use crate::Sticky;
+    use std::thread;
+
+    crate::stack_token!(tok);
+    let val = Sticky::new(String::from("hello"));
+    let weak = val.downgrade();
+    let other = weak.clone();
+    assert_eq!(&*weak.upgrade(tok).unwrap(), "hello");
+    thread::spawn(move || {
+        crate::stack_token!(tok);
+        assert!(!other.is_valid());
+        assert!(other.upgrade(tok).is_none());
+    })
+    .join()
+    .unwrap();
+    drop(val);
+    assert!(weak.upgrade(tok).is_none());
+}
+
+#[test]
+fn test_owner_dropped_while_borrowed() {
+    use crate::Sticky;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let was_called = Arc::new(AtomicBool::new(false));
+    struct X(Arc<AtomicBool>);
+    impl Drop for X {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    crate::stack_token!(tok);
+    let val = Sticky::new(X(was_called.clone()));
+    let weak = val.downgrade();
+    let borrowed = weak.upgrade(tok).unwrap();
+    drop(val);
+    assert!(!was_called.load(Ordering::SeqCst));
+    assert!(!borrowed.0.load(Ordering::SeqCst));
+    assert!(weak.upgrade(tok).is_none());
+    drop(borrowed);
+    assert!(was_called.load(Ordering::SeqCst));
+}
+
+#[test]
+fn test_exclusive_access() {
+    use crate::{AccessErrorReason, Sticky};
+
+    crate::stack_token!(tok);
+    let mut val = Sticky::new(vec![1, 2, 3]);
+    let weak = val.downgrade();
+    assert_eq!(
+        val.try_get_mut(tok).unwrap_err().reason(),
+        AccessErrorReason::Borrowed
+    );
+    {
+        let borrowed = weak.upgrade(tok).unwrap();
+        assert_eq!(borrowed.len(), 3);
+        assert_eq!(*val.get(tok), *borrowed);
+    }
+    drop(weak);
+    val.get_mut(tok).push(4);
+
+    let weak = val.downgrade();
+    let borrowed = weak.upgrade(tok).unwrap();
+    let val = val.try_into_inner().unwrap_err().into_inner();
+    drop(borrowed);
+    assert_eq!(val.into_inner(), vec![1, 2, 3, 4]);
+    assert!(weak.upgrade(tok).is_none());
+}
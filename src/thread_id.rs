@@ -1,13 +1,58 @@
+use std::cell::Cell;
 use std::num::NonZeroUsize;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread::{self, ThreadId};
 
-fn next() -> NonZeroUsize {
+use crate::home::Home;
+
+thread_local!(static THREAD_ID: NonZeroUsize = next());
+thread_local!(static STD_THREAD_ID: Cell<Option<ThreadId>> = Cell::new(None));
+
+pub(crate) fn next() -> NonZeroUsize {
     static COUNTER: AtomicUsize = AtomicUsize::new(1);
     NonZeroUsize::new(COUNTER.fetch_add(1, Ordering::Relaxed))
         .expect("more than usize::MAX threads")
 }
 
-pub(crate) fn get() -> NonZeroUsize {
-    thread_local!(static THREAD_ID: NonZeroUsize = next());
-    THREAD_ID.with(|&x| x)
+/// Returns the cached ID of the current thread.
+///
+/// This is much cheaper than `thread::current().id()` which needs to clone
+/// the thread handle.  Returns `None` if the thread local is no longer
+/// available because the thread is shutting down.
+#[inline(always)]
+pub(crate) fn try_get() -> Option<NonZeroUsize> {
+    THREAD_ID.try_with(|&x| x).ok()
+}
+
+/// Returns the std ID of the current thread.
+///
+/// Unlike `thread::current()` this does not panic once the thread started
+/// tearing down its thread locals.  The ID is cached the first time it is
+/// requested and `None` is returned if it cannot be determined any more.
+pub(crate) fn try_current() -> Option<ThreadId> {
+    STD_THREAD_ID
+        .try_with(|cached| {
+            if cached.get().is_none() {
+                cached.set(Some(thread::current().id()));
+            }
+            cached.get()
+        })
+        .ok()
+        .flatten()
+}
+
+/// Caches the std ID of the current thread for [`try_current`].
+pub(crate) fn remember_current(id: ThreadId) {
+    STD_THREAD_ID.try_with(|cached| cached.set(Some(id))).ok();
+}
+
+/// Returns `true` if `id` which was handed out for `home` is the current thread.
+#[inline(always)]
+pub(crate) fn is_current(id: NonZeroUsize, home: &Home) -> bool {
+    match try_get() {
+        Some(current) => current == id,
+        // the id of a thread never changes, so if we cannot get to it any
+        // more we need to fall back to the slow comparison.
+        None => try_current() == Some(home.thread_id()),
+    }
 }
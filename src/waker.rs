@@ -0,0 +1,160 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Wake, Waker};
+
+use crate::home::Home;
+use crate::policy::ViolationPolicy;
+use crate::stack_token;
+use crate::sticky::Sticky;
+
+/// Turns a thread bound wake callback into a [`Waker`] that can be woken
+/// from any thread.
+///
+/// Local event loops often notify their tasks through non `Send` state such
+/// as an `Rc` based queue.  A [`FragileWaker`] keeps such a callback on the
+/// thread that created it.  Wakes on that thread invoke the callback right
+/// away, wakes from other threads are queued on the [`Home`] of the owning
+/// thread and invoke the callback the next time it calls
+/// [`pump`](crate::pump).  Multiple wakes from foreign threads that happen
+/// before the owning thread gets around to it are coalesced into one.
+///
+/// ```
+/// use std::cell::Cell;
+/// use std::rc::Rc;
+/// use std::thread;
+/// use fragile::FragileWaker;
+///
+/// let woken = Rc::new(Cell::new(0));
+/// let waker = FragileWaker::new({
+///     let woken = woken.clone();
+///     move || woken.set(woken.get() + 1)
+/// })
+/// .into_waker();
+///
+/// thread::spawn(move || waker.wake()).join().unwrap();
+/// assert_eq!(woken.get(), 0);
+/// fragile::pump();
+/// assert_eq!(woken.get(), 1);
+/// ```
+pub struct FragileWaker<W: 'static> {
+    wake: Sticky<W>,
+    home: Home,
+    // set while a wake is queued on the home.
+    queued: AtomicBool,
+}
+
+impl<W: Fn() + 'static> FragileWaker<W> {
+    /// Creates a new [`FragileWaker`] invoking `wake` on the current thread.
+    #[track_caller]
+    pub fn new(wake: W) -> Self {
+        let mut wake = Sticky::new(wake);
+        // the last waker is routinely dropped on a foreign thread and the
+        // callback reclaimed by its home, which is not a violation.
+        wake.set_violation_policy(ViolationPolicy::Leak);
+        FragileWaker {
+            home: wake.home().clone(),
+            wake,
+            queued: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the [`Home`] of the thread that invokes the callback.
+    pub fn home(&self) -> &Home {
+        &self.home
+    }
+
+    /// Converts the [`FragileWaker`] into a [`Waker`].
+    pub fn into_waker(self) -> Waker {
+        Waker::from(Arc::new(self))
+    }
+
+    fn invoke(&self) {
+        stack_token!(tok);
+        (self.wake.get(tok))();
+    }
+}
+
+impl<W: Fn() + 'static> Wake for FragileWaker<W> {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        if self.wake.is_valid() {
+            self.invoke();
+        } else if !self.queued.swap(true, Ordering::AcqRel) {
+            let this = self.clone();
+            self.home.push(Box::new(move || {
+                this.queued.store(false, Ordering::Release);
+                this.invoke();
+            }));
+        }
+    }
+}
+
+impl<W: Fn() + 'static> From<FragileWaker<W>> for Waker {
+    #[inline]
+    fn from(waker: FragileWaker<W>) -> Waker {
+        waker.into_waker()
+    }
+}
+
+impl<W> fmt::Debug for FragileWaker<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FragileWaker")
+            .field("home", &self.home)
+            .finish()
+    }
+}
+
+#[test]
+fn test_wake_on_owner() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    let woken = Rc::new(Cell::new(0));
+    let waker = FragileWaker::new({
+        let woken = woken.clone();
+        move || woken.set(woken.get() + 1)
+    })
+    .into_waker();
+    waker.wake_by_ref();
+    let other = waker.clone();
+    drop(waker);
+    other.wake();
+    assert_eq!(woken.get(), 2);
+    assert_eq!(Rc::strong_count(&woken), 1);
+}
+
+#[test]
+fn test_wake_elsewhere() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use std::thread;
+
+    let woken = Rc::new(Cell::new(0));
+    let waker: Waker = FragileWaker::new({
+        let woken = woken.clone();
+        move || woken.set(woken.get() + 1)
+    })
+    .into();
+    let other = waker.clone();
+    thread::spawn(move || {
+        other.wake_by_ref();
+        other.wake_by_ref();
+        other.wake();
+    })
+    .join()
+    .unwrap();
+    assert_eq!(woken.get(), 0);
+    // the wakes are coalesced into one
+    assert_eq!(crate::pump(), 1);
+    assert_eq!(woken.get(), 1);
+
+    thread::spawn(move || waker.wake()).join().unwrap();
+    assert_eq!(crate::pump(), 1);
+    assert_eq!(woken.get(), 2);
+    // the last waker was dropped elsewhere, the callback is reclaimed here.
+    assert_eq!(Rc::strong_count(&woken), 1);
+}
@@ -0,0 +1,23 @@
+use std::rc::Rc;
+use std::thread;
+
+use fragile::{FragileWaker, ViolationPolicy};
+
+#[test]
+fn test_internal_wrappers_ignore_global_policy() {
+    fragile::set_violation_policy(Some(ViolationPolicy::Panic));
+
+    #[cfg(feature = "future")]
+    {
+        let future = fragile::HomeBound::new(async { 42 });
+        thread::spawn(move || drop(future)).join().unwrap();
+        fragile::pump();
+    }
+
+    let rc = Rc::new(());
+    let waker = FragileWaker::new(move || drop(rc.clone())).into_waker();
+    thread::spawn(move || drop(waker)).join().unwrap();
+    fragile::pump();
+
+    fragile::set_violation_policy(None);
+}
@@ -0,0 +1,46 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use fragile::{Fragile, Sticky, ViolationInfo, ViolationKind, ViolationPolicy};
+
+static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+fn count_drops(info: &ViolationInfo) {
+    assert_eq!(info.kind(), ViolationKind::Drop);
+    DROPS.fetch_add(1, Ordering::SeqCst);
+}
+
+struct X;
+
+impl Drop for X {
+    fn drop(&mut self) {}
+}
+
+#[test]
+fn test_global_policy() {
+    assert!(fragile::violation_policy().is_none());
+    fragile::set_violation_policy(Some(ViolationPolicy::Callback(count_drops)));
+    assert!(matches!(
+        fragile::violation_policy(),
+        Some(ViolationPolicy::Callback(_))
+    ));
+
+    let fragile = Fragile::new(X);
+    let sticky = Sticky::new(X);
+    thread::spawn(move || {
+        drop(fragile);
+        drop(sticky);
+    })
+    .join()
+    .unwrap();
+    assert_eq!(DROPS.load(Ordering::SeqCst), 2);
+
+    // the policy of the value takes precedence
+    let mut fragile = Fragile::new(X);
+    fragile.set_violation_policy(ViolationPolicy::Panic);
+    assert!(thread::spawn(move || drop(fragile)).join().is_err());
+    assert_eq!(DROPS.load(Ordering::SeqCst), 2);
+
+    fragile::set_violation_policy(None);
+    assert!(fragile::violation_policy().is_none());
+}
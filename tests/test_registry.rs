@@ -0,0 +1,40 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use fragile::registry::{self, Snapshot};
+use fragile::Sticky;
+
+static LEAKED: AtomicUsize = AtomicUsize::new(0);
+
+fn report(snapshot: &Snapshot) {
+    assert_eq!(snapshot.stats().entries(), snapshot.entries().len());
+    // the report is produced during teardown without touching the home.
+    assert_eq!(snapshot.stats().pending_remote_drops(), 0);
+    for ty in snapshot.types() {
+        if ty.type_name() == std::any::type_name::<String>() {
+            LEAKED.fetch_add(ty.count(), Ordering::SeqCst);
+        }
+    }
+}
+
+#[test]
+fn test_exit_report() {
+    registry::set_exit_report(Some(report));
+
+    let kept = thread::spawn(|| {
+        let kept = Sticky::new(String::from("kept"));
+        std::mem::forget(Sticky::new(String::from("forgotten")));
+        drop(Sticky::new(String::from("dropped")));
+        kept
+    })
+    .join()
+    .unwrap();
+    assert_eq!(LEAKED.load(Ordering::SeqCst), 2);
+    assert!(kept.is_orphaned());
+
+    registry::set_exit_report(None);
+    thread::spawn(|| std::mem::forget(Sticky::new(String::new())))
+        .join()
+        .unwrap();
+    assert_eq!(LEAKED.load(Ordering::SeqCst), 2);
+}